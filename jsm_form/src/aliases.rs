@@ -0,0 +1,247 @@
+use crate::schema::{FieldIssue, IssueKind, RequestTypeFields};
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Jira system field IDs accepted in ticket files when there is no request type schema
+/// to check keys against. With a schema, its field list decides instead.
+const SYSTEM_FIELDS: &[&str] = &[
+    "summary",
+    "description",
+    "priority",
+    "components",
+    "duedate",
+    "labels",
+    "environment",
+    "assignee",
+    "reporter",
+    "attachment",
+    "fixVersions",
+    "versions",
+    "security",
+    "timetracking",
+    "issuelinks",
+    "parent",
+];
+
+/// Returns true if `key` looks like a field ID: `customfield_<n>` or a system field
+pub fn is_field_id(key: &str) -> bool {
+    if let Some(number) = key.strip_prefix("customfield_") {
+        return !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+    }
+    SYSTEM_FIELDS.contains(&key)
+}
+
+/// Report resolved keys that are not field IDs, i.e. neither a configured alias nor a
+/// field ID was written. Used when no request type schema is available.
+pub fn check_field_ids(fields: &BTreeMap<String, Value>) -> Vec<FieldIssue> {
+    fields
+        .keys()
+        .filter(|key| !is_field_id(key))
+        .map(|key| FieldIssue {
+            field_id: key.clone(),
+            field_name: None,
+            kind: IssueKind::UnknownField,
+            message: "is neither a configured alias nor a field ID".to_string(),
+        })
+        .collect()
+}
+
+/// Replace alias keys with the field IDs they map to.
+///
+/// Any other key is taken to be a field ID and passes through unchanged. It is checked
+/// against the request type's field list by [`crate::schema::validate_fields`], or by
+/// [`check_field_ids`] when there is no field list.
+/// Aliases match case-insensitively, because the layered config lower-cases table keys.
+pub fn resolve_aliases(
+    fields: HashMap<String, Value>,
+    aliases: &BTreeMap<String, String>,
+) -> Result<HashMap<String, Value>> {
+    let mut resolved = HashMap::with_capacity(fields.len());
+    let mut sources: HashMap<String, String> = HashMap::new();
+
+    for (key, value) in fields {
+        let field_id = lookup_alias(aliases, &key)
+            .cloned()
+            .unwrap_or_else(|| key.clone());
+
+        if let Some(previous) = sources.insert(field_id.clone(), key.clone()) {
+            return Err(anyhow::anyhow!(
+                "Field {} is set twice, via '{}' and '{}'",
+                field_id,
+                previous,
+                key
+            ));
+        }
+        resolved.insert(field_id, value);
+    }
+
+    Ok(resolved)
+}
+
+/// Look up an alias, preferring an exact match over a case-insensitive one
+fn lookup_alias<'a>(aliases: &'a BTreeMap<String, String>, key: &str) -> Option<&'a String> {
+    aliases.get(key).or_else(|| {
+        aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(key))
+            .map(|(_, field_id)| field_id)
+    })
+}
+
+/// Turn a field display name into an alias, e.g. "Planned start" -> "planned_start"
+pub fn alias_for_name(name: &str) -> String {
+    let mut alias = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            alias.push(c.to_ascii_lowercase());
+        } else if !alias.ends_with('_') {
+            alias.push('_');
+        }
+    }
+    alias.trim_matches('_').to_string()
+}
+
+/// Generate an alias table from the display names of a request type's custom fields
+pub fn generate_alias_table(schema: &RequestTypeFields) -> BTreeMap<String, String> {
+    let mut table = BTreeMap::new();
+    for field in &schema.request_type_fields {
+        if !field.field_id.starts_with("customfield_") {
+            continue;
+        }
+        let mut alias = alias_for_name(&field.name);
+        if alias.is_empty() || schema.field(&alias).is_some() || table.contains_key(&alias) {
+            alias = format!(
+                "{}_{}",
+                alias,
+                field.field_id.trim_start_matches("customfield_")
+            )
+            .trim_start_matches('_')
+            .to_string();
+        }
+        table.insert(alias, field.field_id.clone());
+    }
+    table
+}
+
+/// Render an alias table as a TOML `[aliases]` section, annotated with display names
+pub fn render_alias_table(table: &BTreeMap<String, String>, schema: &RequestTypeFields) -> String {
+    let mut out = String::from("[aliases]\n");
+    for (alias, field_id) in table {
+        let name = schema
+            .field(field_id)
+            .map(|field| field.name.as_str())
+            .unwrap_or_default();
+        out.push_str(&format!("{} = \"{}\"  # {}\n", alias, field_id, name));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn aliases() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("planned_start".to_string(), "customfield_10878".to_string()),
+            (
+                "implementation_plan".to_string(),
+                "customfield_10883".to_string(),
+            ),
+        ])
+    }
+
+    #[test]
+    fn resolves_aliases_and_keeps_field_ids() {
+        let fields = HashMap::from([
+            ("summary".to_string(), json!("Deploy")),
+            (
+                "planned_start".to_string(),
+                json!("2025-09-23T14:00:00.000+1300"),
+            ),
+            (
+                "customfield_10879".to_string(),
+                json!("2025-09-23T16:00:00.000+1300"),
+            ),
+        ]);
+        let resolved = resolve_aliases(fields, &aliases()).unwrap();
+        assert_eq!(resolved.len(), 3);
+        assert!(resolved.contains_key("customfield_10878"));
+        assert!(resolved.contains_key("customfield_10879"));
+        assert!(resolved.contains_key("summary"));
+    }
+
+    #[test]
+    fn checks_other_keys_as_field_ids_and_rejects_duplicates() {
+        // System fields such as `timetracking` need no alias; typos pass through to be
+        // reported by validation
+        let other = HashMap::from([
+            (
+                "timetracking".to_string(),
+                json!({ "originalEstimate": "1h" }),
+            ),
+            ("planed_start".to_string(), json!("x")),
+        ]);
+        let resolved = resolve_aliases(other, &aliases()).unwrap();
+        assert!(resolved.contains_key("timetracking"));
+        assert!(resolved.contains_key("planed_start"));
+
+        // Without a schema, only the typo is rejected
+        let issues = check_field_ids(&resolved.into_iter().collect());
+        let keys: Vec<&str> = issues.iter().map(|issue| issue.field_id.as_str()).collect();
+        assert_eq!(keys, vec!["planed_start"]);
+        assert_eq!(issues[0].kind, IssueKind::UnknownField);
+
+        let duplicate = HashMap::from([
+            ("planned_start".to_string(), json!("a")),
+            ("customfield_10878".to_string(), json!("b")),
+        ]);
+        assert!(resolve_aliases(duplicate, &aliases()).is_err());
+    }
+
+    #[test]
+    fn resolves_mixed_case_aliases_from_the_layered_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jsm_config.toml");
+        std::fs::write(
+            &path,
+            "org = \"acme\"\nbase_url = \"https://acme.atlassian.net\"\n\
+             [aliases]\nPlannedStart = \"customfield_10878\"\n",
+        )
+        .unwrap();
+        let mut sources = crate::config::ConfigSources::new(&path);
+        sources.system_file = None;
+        sources.user_file = None;
+        sources.env = Some(HashMap::new());
+        let config = crate::config::load_layered(&sources).unwrap();
+
+        let fields = HashMap::from([("PlannedStart".to_string(), json!("tomorrow 09:00"))]);
+        let resolved = resolve_aliases(fields, &config.aliases).unwrap();
+        assert_eq!(resolved["customfield_10878"], json!("tomorrow 09:00"));
+
+        let duplicate = HashMap::from([
+            ("PlannedStart".to_string(), json!("a")),
+            ("plannedstart".to_string(), json!("b")),
+        ]);
+        assert!(resolve_aliases(duplicate, &config.aliases).is_err());
+    }
+
+    #[test]
+    fn generates_aliases_from_display_names() {
+        let schema: RequestTypeFields = serde_json::from_value(json!({
+            "requestTypeFields": [
+                { "fieldId": "summary", "name": "Summary" },
+                { "fieldId": "customfield_10878", "name": "Planned start" },
+                { "fieldId": "customfield_10243", "name": "Affected services" },
+                { "fieldId": "customfield_10300", "name": "Affected services" }
+            ]
+        }))
+        .unwrap();
+        let table = generate_alias_table(&schema);
+        assert_eq!(table["planned_start"], "customfield_10878");
+        assert_eq!(table["affected_services"], "customfield_10243");
+        assert_eq!(table["affected_services_10300"], "customfield_10300");
+        assert!(!table.values().any(|id| id == "summary"));
+    }
+}
@@ -0,0 +1,285 @@
+use crate::JsmConfig;
+use crate::error::JsmError;
+use anyhow::{Context, Result};
+use reqwest::Client;
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// Response from uploading temporary attachments
+#[derive(Debug, Deserialize)]
+struct TemporaryAttachments {
+    #[serde(rename = "temporaryAttachments")]
+    temporary_attachments: Vec<TemporaryAttachment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TemporaryAttachment {
+    #[serde(rename = "temporaryAttachmentId")]
+    temporary_attachment_id: String,
+    #[serde(rename = "fileName")]
+    file_name: String,
+}
+
+/// Check that every attachment exists before anything is created
+pub fn check_attachments(paths: &[PathBuf]) -> Result<()> {
+    let missing: Vec<String> = paths
+        .iter()
+        .filter(|path| !path.is_file())
+        .map(|path| path.display().to_string())
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Attachment file(s) not found: {}",
+            missing.join(", ")
+        ))
+    }
+}
+
+/// Upload files as temporary attachments of a service desk and return their IDs
+pub async fn upload_temporary_files(
+    client: &Client,
+    config: &JsmConfig,
+    service_desk_id: &str,
+    paths: &[PathBuf],
+) -> Result<Vec<String>> {
+    let upload_url = format!(
+        "{}/rest/servicedeskapi/servicedesk/{}/attachTemporaryFile",
+        config.base_url, service_desk_id
+    );
+
+    let mut form = Form::new();
+    for path in paths {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read attachment: {}", path.display()))?;
+        form = form.part("file", Part::bytes(bytes).file_name(file_name(path)));
+    }
+
+    let response = crate::http::send(
+        &config.http,
+        client
+            .post(&upload_url)
+            .basic_auth(
+                &config.auth.username,
+                Some(&config.auth.token_atlassian_api),
+            )
+            .header("Accept", "application/json")
+            .header("X-ExperimentalApi", "opt-in")
+            .header("X-Atlassian-Token", "no-check")
+            .multipart(form),
+    )
+    .await
+    .context("Failed to upload attachments")?;
+
+    if !response.status().is_success() {
+        return Err(JsmError::from_response(response).await.into());
+    }
+
+    let uploaded: TemporaryAttachments = response
+        .json()
+        .await
+        .context("Failed to parse attachment upload response")?;
+    for attachment in &uploaded.temporary_attachments {
+        crate::log_debug!(
+            "Uploaded temporary attachment {} ({})",
+            attachment.file_name,
+            attachment.temporary_attachment_id
+        );
+    }
+    Ok(uploaded
+        .temporary_attachments
+        .into_iter()
+        .map(|attachment| attachment.temporary_attachment_id)
+        .collect())
+}
+
+/// Upload files to the request's own service desk and attach them to the request
+pub async fn attach_files(
+    client: &Client,
+    config: &JsmConfig,
+    service_desk_id: &str,
+    issue_key: &str,
+    paths: &[PathBuf],
+) -> Result<()> {
+    check_attachments(paths)?;
+    let temporary_ids = upload_temporary_files(client, config, service_desk_id, paths).await?;
+
+    let attach_url = format!(
+        "{}/rest/servicedeskapi/request/{}/attachment",
+        config.base_url, issue_key
+    );
+
+    let response = crate::http::send(
+        &config.http,
+        client
+            .post(&attach_url)
+            .basic_auth(
+                &config.auth.username,
+                Some(&config.auth.token_atlassian_api),
+            )
+            .header("Accept", "application/json")
+            .header("Content-Type", "application/json")
+            .json(&json!({
+                "temporaryAttachmentIds": temporary_ids,
+                "public": true,
+            })),
+    )
+    .await
+    .with_context(|| format!("Failed to attach files to {}", issue_key))?;
+
+    if response.status().is_success() {
+        crate::log_info!("Attached {} file(s) to {}", paths.len(), issue_key);
+        Ok(())
+    } else {
+        Err(JsmError::from_response(response).await.into())
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_json, header, header_regex, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const UPLOAD_PATH: &str = "/rest/servicedeskapi/servicedesk/6/attachTemporaryFile";
+    const ATTACH_PATH: &str = "/rest/servicedeskapi/request/ITH-1/attachment";
+
+    async fn setup() -> (MockServer, JsmConfig, tempfile::TempDir, PathBuf) {
+        let server = MockServer::start().await;
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        config.http.max_retries = 0;
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("runbook.txt");
+        std::fs::write(&file, "steps").unwrap();
+        (server, config, dir, file)
+    }
+
+    fn uploaded(id: &str) -> ResponseTemplate {
+        ResponseTemplate::new(201).set_body_json(json!({
+            "temporaryAttachments": [{ "temporaryAttachmentId": id, "fileName": "runbook.txt" }]
+        }))
+    }
+
+    #[tokio::test]
+    async fn uploads_then_attaches_the_temporary_files() {
+        let (server, config, _dir, file) = setup().await;
+        Mock::given(method("POST"))
+            .and(path(UPLOAD_PATH))
+            .and(header("X-Atlassian-Token", "no-check"))
+            .and(header("X-ExperimentalApi", "opt-in"))
+            .and(header_regex(
+                "Content-Type",
+                "^multipart/form-data; boundary=",
+            ))
+            .respond_with(uploaded("temp-1"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(ATTACH_PATH))
+            .and(body_json(json!({
+                "temporaryAttachmentIds": ["temp-1"],
+                "public": true,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        attach_files(
+            &Client::new(),
+            &config,
+            "6",
+            "ITH-1",
+            std::slice::from_ref(&file),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejected_uploads_are_typed_errors() {
+        let (server, config, _dir, file) = setup().await;
+        Mock::given(method("POST"))
+            .and(path(UPLOAD_PATH))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(ATTACH_PATH))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let error = attach_files(
+            &Client::new(),
+            &config,
+            "6",
+            "ITH-1",
+            std::slice::from_ref(&file),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<JsmError>(),
+            Some(JsmError::Auth { url, .. }) if url.ends_with(UPLOAD_PATH)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejected_attach_calls_are_typed_errors() {
+        let (server, config, _dir, file) = setup().await;
+        Mock::given(method("POST"))
+            .and(path(UPLOAD_PATH))
+            .respond_with(uploaded("temp-2"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(ATTACH_PATH))
+            .respond_with(
+                ResponseTemplate::new(400)
+                    .set_body_json(json!({ "errorMessage": "Attachments are disabled" })),
+            )
+            .mount(&server)
+            .await;
+
+        let error = attach_files(
+            &Client::new(),
+            &config,
+            "6",
+            "ITH-1",
+            std::slice::from_ref(&file),
+        )
+        .await
+        .unwrap_err();
+        let error = error.downcast_ref::<JsmError>().unwrap();
+        assert_eq!(error.status(), Some(400));
+        assert!(
+            error.to_string().contains("Attachments are disabled"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn reports_every_missing_attachment() {
+        let error = check_attachments(&[
+            PathBuf::from("missing-a.txt"),
+            PathBuf::from("missing-b.txt"),
+        ])
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("missing-a.txt, missing-b.txt"), "{}", error);
+    }
+}
@@ -1,20 +1,100 @@
 use crate::AuthConfig;
-use anyhow::{Context, Result};
+use crate::error::{JsmError, Result};
+use crate::http::HttpConfig;
 use reqwest::Client;
+use std::process::{Command, Stdio};
+
+/// Placeholder values written by `init --defaults`, treated as not set
+const PLACEHOLDERS: &[&str] = &[
+    "your-api-token-here",
+    "your-password",
+    "your-microsoft-password",
+];
+
+/// Whether a secret from the config is missing or still a placeholder
+pub fn is_unset(secret: &str) -> bool {
+    let secret = secret.trim();
+    secret.is_empty() || PLACEHOLDERS.contains(&secret)
+}
+
+/// Fill in the API token from `credential_helper` when it is not set in the config
+pub fn apply_token_helper(auth: &mut AuthConfig) -> Result<()> {
+    if is_unset(&auth.token_atlassian_api)
+        && let Some(helper) = &auth.credential_helper
+    {
+        auth.token_atlassian_api = run_credential_helper(helper)?;
+    }
+    Ok(())
+}
+
+/// Fill in the Microsoft password from `microsoft_password_helper` when it is not set in
+/// the config. Only the browser login needs it, so REST-only commands never run it.
+pub fn apply_microsoft_password_helper(auth: &mut AuthConfig) -> Result<()> {
+    if is_unset(&auth.microsoft_password)
+        && let Some(helper) = &auth.microsoft_password_helper
+    {
+        auth.microsoft_password = run_credential_helper(helper)?;
+    }
+    Ok(())
+}
+
+/// Run a credential helper through the shell and return the first line it prints.
+/// Like git credential helpers, it can prompt on the terminal and report on stderr.
+pub fn run_credential_helper(command: &str) -> Result<String> {
+    let helper_error = |message: String| JsmError::Config {
+        path: None,
+        message: format!("credential helper `{}` {}", command, message),
+    };
+    crate::log_debug!("Running credential helper `{}`", command);
+
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let output = shell
+        .arg(command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| helper_error(format!("could not be started: {}", e)))?;
+    if !output.status.success() {
+        return Err(helper_error(format!("failed with {}", output.status)));
+    }
+
+    // Only the first line: `pass` keeps notes below the secret
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|_| helper_error("printed invalid UTF-8".to_string()))?;
+    let secret = stdout.lines().next().unwrap_or_default();
+    if secret.trim().is_empty() {
+        return Err(helper_error("printed nothing".to_string()));
+    }
+    Ok(secret.to_string())
+}
 
 /// Authenticate with the JSM instance using HTTP Basic Authentication
 /// This method validates the credentials by making a test API call to the service desk
-pub async fn authenticate(client: &Client, auth: &AuthConfig, base_url: &str) -> Result<()> {
+pub async fn authenticate(
+    client: &Client,
+    auth: &AuthConfig,
+    base_url: &str,
+    http: &HttpConfig,
+) -> Result<()> {
     // For Atlassian Cloud instances, we use HTTP Basic Authentication with email:api_token
     // Test authentication by making a simple API call to get service desk info
     let test_url = format!("{}/rest/servicedeskapi/servicedesk", base_url);
 
-    let response = client
-        .get(&test_url)
-        .basic_auth(&auth.username, Some(&auth.token_atlassian_api))
-        .send()
-        .await
-        .context("Failed to test authentication")?;
+    let response = crate::http::send(
+        http,
+        client
+            .get(&test_url)
+            .basic_auth(&auth.username, Some(&auth.token_atlassian_api)),
+    )
+    .await?;
 
     if response.status().is_success() {
         crate::log_info!("Authentication successful");
@@ -24,26 +104,120 @@ pub async fn authenticate(client: &Client, auth: &AuthConfig, base_url: &str) ->
         let error_body = response.text().await.unwrap_or_default();
 
         if status == 401 {
-            Err(anyhow::anyhow!(
-                "status=401 Authentication failed: Invalid credentials. Make sure you're using:\n\
-                - Email address as username\n\
-                - API token as password (not your account password)\n\
-                Create an API token at: https://id.atlassian.com/manage-profile/security/api-tokens\n\
-                Error details: {}\n",
-                error_body
-            ))
+            Err(JsmError::Auth {
+                status: status.as_u16(),
+                url: test_url,
+                message: format!(
+                    "Invalid credentials. Make sure you're using:\n\
+                    - Email address as username\n\
+                    - API token as password (not your account password)\n\
+                    Create an API token at: https://id.atlassian.com/manage-profile/security/api-tokens\n\
+                    Error details: {}",
+                    error_body
+                ),
+            })
         } else if status == 403 {
-            Err(anyhow::anyhow!(
-                "status=403 Authentication successful but access denied. You may not have permission to access this service desk.\n\
-                Error details: {}\n",
-                error_body
-            ))
+            Err(JsmError::Forbidden {
+                status: status.as_u16(),
+                url: test_url,
+                message: format!(
+                    "Authentication successful but access denied. You may not have permission to access this service desk.\n\
+                    Error details: {}",
+                    error_body
+                ),
+            })
         } else {
-            Err(anyhow::anyhow!(
-                "Authentication failed with status: {} - {}\n",
-                status,
-                error_body
-            ))
+            Err(JsmError::from_status(status.as_u16(), test_url, error_body))
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Write an executable shell script into `dir`
+    fn stub_script(dir: &tempfile::TempDir, name: &str, body: &str) -> std::path::PathBuf {
+        let path = dir.path().join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn auth(token: &str, helper: Option<String>) -> AuthConfig {
+        AuthConfig {
+            username: "me@example.com".to_string(),
+            token_atlassian_api: token.to_string(),
+            microsoft_password: String::new(),
+            credential_helper: helper,
+            microsoft_password_helper: None,
         }
     }
+
+    #[test]
+    fn helpers_fill_in_unset_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = stub_script(
+            &dir,
+            "helper",
+            "echo \"secret-for-$1\"\necho \"url: https://id.atlassian.com\"",
+        );
+        let helper = format!("{} jsm/token", script.display());
+
+        let mut from_helper = auth("", Some(helper.clone()));
+        from_helper.microsoft_password_helper = Some(format!("{} microsoft", script.display()));
+        apply_token_helper(&mut from_helper).unwrap();
+        assert_eq!(from_helper.token_atlassian_api, "secret-for-jsm/token");
+        apply_microsoft_password_helper(&mut from_helper).unwrap();
+        assert_eq!(from_helper.microsoft_password, "secret-for-microsoft");
+
+        // Placeholders count as unset; real values are kept and the helper is not run
+        let mut placeholder = auth("your-api-token-here", Some(helper.clone()));
+        apply_token_helper(&mut placeholder).unwrap();
+        assert_eq!(placeholder.token_atlassian_api, "secret-for-jsm/token");
+        let mut explicit = auth("from-config", Some(helper));
+        apply_token_helper(&mut explicit).unwrap();
+        assert_eq!(explicit.token_atlassian_api, "from-config");
+    }
+
+    #[test]
+    fn the_token_step_never_runs_the_microsoft_helper() {
+        let dir = tempfile::tempdir().unwrap();
+        let token = stub_script(&dir, "token_helper", "echo token");
+        let marker = dir.path().join("microsoft_helper_ran");
+        let microsoft = stub_script(
+            &dir,
+            "microsoft_helper",
+            &format!("touch {}\nexit 1", marker.display()),
+        );
+
+        let mut only_token = auth("", Some(token.display().to_string()));
+        only_token.microsoft_password_helper = Some(microsoft.display().to_string());
+        apply_token_helper(&mut only_token).unwrap();
+        assert_eq!(only_token.token_atlassian_api, "token");
+        assert!(only_token.microsoft_password.is_empty());
+        assert!(!marker.exists());
+
+        // The browser step does run it, and a broken helper fails there
+        assert!(apply_microsoft_password_helper(&mut only_token).is_err());
+        assert!(marker.exists());
+    }
+
+    #[test]
+    fn failing_or_silent_helpers_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let failing = stub_script(&dir, "failing_helper", "echo 'not found' >&2\nexit 2");
+        let error = run_credential_helper(&failing.display().to_string())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("failed with exit status: 2"), "{}", error);
+        assert!(error.contains(&failing.display().to_string()), "{}", error);
+
+        let silent = stub_script(&dir, "silent_helper", "true");
+        let error = run_credential_helper(&silent.display().to_string())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("printed nothing"), "{}", error);
+    }
 }
@@ -1,21 +1,458 @@
+use crate::error::{JsmError, Result};
 use crate::{AuthConfig, JsmConfig};
-use anyhow::Result;
-use std::path::Path;
+use ::config::builder::{ConfigBuilder, DefaultState};
+use ::config::{Config, ConfigError, Environment, File, FileFormat, Map, Source, Value, ValueKind};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Prefix of environment variables overriding config values; nested keys are separated
+/// by `__`, e.g. `JSM_AUTH__TOKEN_ATLASSIAN_API`
+pub const ENV_PREFIX: &str = "JSM";
+
+/// System-wide config file, the lowest file layer
+pub const SYSTEM_CONFIG_FILE: &str = "/etc/jsm_form/config.toml";
+
+/// Config keys whose values are masked when shown
+const SECRET_KEYS: &[&str] = &["auth.token_atlassian_api", "auth.microsoft_password"];
 
 /// Load configuration from a file
 pub fn load_config<P: AsRef<Path>>(path: P) -> Result<JsmConfig> {
-    let contents = std::fs::read_to_string(path)?;
-    let config: JsmConfig = toml::from_str(&contents)?;
+    let path = path.as_ref();
+    let config_error = |message: String| JsmError::Config {
+        path: Some(path.to_path_buf()),
+        message,
+    };
+    let contents = std::fs::read_to_string(path).map_err(|e| config_error(e.to_string()))?;
+    let config: JsmConfig = toml::from_str(&contents).map_err(|e| config_error(e.to_string()))?;
     Ok(config)
 }
 
 /// Save configuration to a file
 pub fn save_config<P: AsRef<Path>>(config: &JsmConfig, path: P) -> Result<()> {
-    let contents = toml::to_string_pretty(config)?;
-    std::fs::write(path, contents)?;
+    let path = path.as_ref();
+    let config_error = |message: String| JsmError::Config {
+        path: Some(path.to_path_buf()),
+        message,
+    };
+    let contents = toml::to_string_pretty(config).map_err(|e| config_error(e.to_string()))?;
+    std::fs::write(path, contents).map_err(|e| config_error(e.to_string()))?;
     Ok(())
 }
 
+/// User config file: `$XDG_CONFIG_HOME/jsm_form/config.toml`, else
+/// `~/.config/jsm_form/config.toml`
+pub fn user_config_file() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("jsm_form").join("config.toml"))
+}
+
+/// The layers a configuration is built from, lowest priority first: built-in defaults,
+/// system file, user file, project file, the selected profile, `JSM_*` environment
+/// variables, overrides
+#[derive(Debug, Clone)]
+pub struct ConfigSources {
+    pub system_file: Option<PathBuf>,
+    pub user_file: Option<PathBuf>,
+    /// The file given with `--config`
+    pub project_file: PathBuf,
+    /// Environment variables to read; `None` reads the process environment
+    pub env: Option<HashMap<String, String>>,
+    /// `key=value` overrides from the command line, e.g. `http.max_retries=0`
+    pub overrides: Vec<(String, String)>,
+}
+
+impl ConfigSources {
+    /// The standard layers around a project file
+    pub fn new(project_file: impl Into<PathBuf>) -> Self {
+        Self {
+            system_file: Some(PathBuf::from(SYSTEM_CONFIG_FILE)),
+            user_file: user_config_file(),
+            project_file: project_file.into(),
+            env: None,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Add `key=value` overrides
+    pub fn with_overrides<S: AsRef<str>>(mut self, overrides: &[S]) -> Result<Self> {
+        for entry in overrides {
+            let (key, value) = entry
+                .as_ref()
+                .split_once('=')
+                .ok_or_else(|| JsmError::Config {
+                    path: None,
+                    message: format!("override '{}' is not of the form key=value", entry.as_ref()),
+                })?;
+            self.overrides
+                .push((key.trim().to_string(), value.trim().to_string()));
+        }
+        Ok(self)
+    }
+
+    /// The config files with their layer names, lowest priority first
+    pub fn files(&self) -> Vec<(&'static str, &Path)> {
+        let mut files = Vec::new();
+        if let Some(path) = &self.system_file {
+            files.push(("system", path.as_path()));
+        }
+        if let Some(path) = &self.user_file {
+            files.push(("user", path.as_path()));
+        }
+        files.push(("project", self.project_file.as_path()));
+        files
+    }
+
+    fn error(&self, message: String) -> JsmError {
+        JsmError::Config {
+            path: Some(self.project_file.clone()),
+            message,
+        }
+    }
+
+    /// All layers, with the selected profile (if any) between the files and the environment
+    fn build(&self) -> Result<Config> {
+        let base = self.build_without_profile()?;
+        match ProfileLayer::selected(self, &base)? {
+            Some(profile) => self
+                .builder(Some(profile))?
+                .build()
+                .map_err(|e| self.error(e.to_string())),
+            None => Ok(base),
+        }
+    }
+
+    fn build_without_profile(&self) -> Result<Config> {
+        self.builder(None)?
+            .build()
+            .map_err(|e| self.error(e.to_string()))
+    }
+
+    fn builder(&self, profile: Option<ProfileLayer>) -> Result<ConfigBuilder<DefaultState>> {
+        if !self.files().iter().any(|(_, path)| path.is_file()) {
+            return Err(
+                self.error("file not found (and no system or user config file)".to_string())
+            );
+        }
+
+        let mut builder = Config::builder().add_source(
+            Config::try_from(&built_in_defaults()).map_err(|e| self.error(e.to_string()))?,
+        );
+        for (_, path) in self.files() {
+            builder = builder.add_source(File::from(path).format(FileFormat::Toml).required(false));
+        }
+        if let Some(profile) = profile {
+            builder = builder.add_source(profile);
+        }
+        builder = builder.add_source(
+            Environment::with_prefix(ENV_PREFIX)
+                .prefix_separator("_")
+                .separator("__")
+                .source(self.env.clone().map(|env| env.into_iter().collect())),
+        );
+        for (key, value) in &self.overrides {
+            builder = builder
+                .set_override(key.as_str(), value.as_str())
+                .map_err(|e| self.error(e.to_string()))?;
+        }
+        Ok(builder)
+    }
+}
+
+/// Values every layer starts from. Credentials default to empty so they can come from
+/// the environment alone, or be prompted for.
+fn built_in_defaults() -> serde_json::Value {
+    serde_json::json!({
+        "portal_id": 0,
+        "request_type_id": 0,
+        "auth": {
+            "username": "",
+            "token_atlassian_api": "",
+            "microsoft_password": "",
+        },
+        "http": crate::http::HttpConfig::default(),
+        "schema_cache": crate::snapshot::SchemaCacheConfig::default(),
+    })
+}
+
+/// Load the configuration from all layers of `sources`, the selected profile included
+pub fn load_layered(sources: &ConfigSources) -> Result<JsmConfig> {
+    sources
+        .build()?
+        .try_deserialize()
+        .map_err(|e| sources.error(e.to_string()))
+}
+
+/// Load the configuration from all layers of `sources` without applying a profile
+pub fn load_layered_base(sources: &ConfigSources) -> Result<JsmConfig> {
+    sources
+        .build_without_profile()?
+        .try_deserialize()
+        .map_err(|e| sources.error(e.to_string()))
+}
+
+/// A `[profiles.<name>]` section. Values left out keep those of the base configuration.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProfileConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub portal_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_type_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_desk: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_type: Option<String>,
+    #[serde(default)]
+    pub auth: ProfileAuthConfig,
+}
+
+/// Credentials of a profile (`[profiles.<name>.auth]`)
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProfileAuthConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_atlassian_api: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub microsoft_password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_helper: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub microsoft_password_helper: Option<String>,
+}
+
+/// The values of the selected `[profiles.<name>]` section, as a layer between the config
+/// files and the environment. A profile that sets the service desk or request type, by ID
+/// or name, replaces both forms of the file value.
+#[derive(Debug, Clone)]
+struct ProfileLayer {
+    name: String,
+    values: Vec<(&'static str, ValueKind)>,
+}
+
+impl ProfileLayer {
+    /// The layer of the profile selected in `config`, if one is
+    fn selected(sources: &ConfigSources, config: &Config) -> Result<Option<Self>> {
+        let name = match config.get_string("profile") {
+            Ok(name) => name,
+            Err(ConfigError::NotFound(_)) => return Ok(None),
+            Err(e) => return Err(sources.error(e.to_string())),
+        };
+        let profiles: BTreeMap<String, ProfileConfig> = match config.get("profiles") {
+            Ok(profiles) => profiles,
+            Err(ConfigError::NotFound(_)) => BTreeMap::new(),
+            Err(e) => return Err(sources.error(e.to_string())),
+        };
+        let profile = find_profile(&profiles, &name)?;
+        Ok(Some(Self::new(&name, profile)))
+    }
+
+    fn new(name: &str, profile: &ProfileConfig) -> Self {
+        let mut values = Vec::new();
+        let text = |value: &Option<String>| match value {
+            Some(value) => ValueKind::String(value.clone()),
+            None => ValueKind::Nil,
+        };
+        if let Some(base_url) = &profile.base_url {
+            values.push((
+                "base_url",
+                ValueKind::String(base_url.trim_end_matches('/').to_string()),
+            ));
+            if profile.org.is_none()
+                && let Some(host) = Url::parse(base_url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(str::to_string))
+            {
+                let org = host.split('.').next().unwrap_or(&host).to_string();
+                values.push(("org", ValueKind::String(org)));
+            }
+        }
+        if let Some(org) = &profile.org {
+            values.push(("org", ValueKind::String(org.clone())));
+        }
+        if profile.portal_id.is_some() || profile.service_desk.is_some() {
+            values.push((
+                "portal_id",
+                ValueKind::U64(profile.portal_id.unwrap_or(0).into()),
+            ));
+            values.push(("service_desk", text(&profile.service_desk)));
+        }
+        if profile.request_type_id.is_some() || profile.request_type.is_some() {
+            values.push((
+                "request_type_id",
+                ValueKind::U64(profile.request_type_id.unwrap_or(0).into()),
+            ));
+            values.push(("request_type", text(&profile.request_type)));
+        }
+        let auth = &profile.auth;
+        if let Some(username) = &auth.username {
+            values.push(("auth.username", ValueKind::String(username.clone())));
+        }
+        // A profile's helper replaces a file secret, which belongs to another account
+        if let Some(helper) = &auth.credential_helper {
+            values.push(("auth.credential_helper", ValueKind::String(helper.clone())));
+            values.push((
+                "auth.token_atlassian_api",
+                ValueKind::String(auth.token_atlassian_api.clone().unwrap_or_default()),
+            ));
+        } else if let Some(token) = &auth.token_atlassian_api {
+            values.push(("auth.token_atlassian_api", ValueKind::String(token.clone())));
+        }
+        if let Some(helper) = &auth.microsoft_password_helper {
+            values.push((
+                "auth.microsoft_password_helper",
+                ValueKind::String(helper.clone()),
+            ));
+            values.push((
+                "auth.microsoft_password",
+                ValueKind::String(auth.microsoft_password.clone().unwrap_or_default()),
+            ));
+        } else if let Some(password) = &auth.microsoft_password {
+            values.push((
+                "auth.microsoft_password",
+                ValueKind::String(password.clone()),
+            ));
+        }
+        Self {
+            name: name.to_string(),
+            values,
+        }
+    }
+}
+
+impl Source for ProfileLayer {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> std::result::Result<Map<String, Value>, ConfigError> {
+        let origin = format!("profile {}", self.name);
+        Ok(self
+            .values
+            .iter()
+            .map(|(key, kind)| (key.to_string(), Value::new(Some(&origin), kind.clone())))
+            .collect())
+    }
+}
+
+/// Look up a profile by name. The config loader lower-cases keys, profile names included.
+fn find_profile<'a>(
+    profiles: &'a BTreeMap<String, ProfileConfig>,
+    name: &str,
+) -> Result<&'a ProfileConfig> {
+    profiles
+        .get(name)
+        .or_else(|| {
+            profiles
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, profile)| profile)
+        })
+        .ok_or_else(|| JsmError::Config {
+            path: None,
+            message: if profiles.is_empty() {
+                format!(
+                    "unknown profile '{}'; no [profiles.<name>] sections are defined",
+                    name
+                )
+            } else {
+                format!(
+                    "unknown profile '{}'. Available profiles: {}",
+                    name,
+                    profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                )
+            },
+        })
+}
+
+/// An effective config value and the layer it came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedValue {
+    /// Dotted key, e.g. `auth.username`
+    pub key: String,
+    /// The value, masked for secrets
+    pub value: String,
+    /// `default`, `<layer> file <path>`, `profile <name>`, `env <variable>` or `--set`
+    pub source: String,
+}
+
+/// Every effective config value with its source, secrets masked
+pub fn resolve_with_sources(sources: &ConfigSources) -> Result<Vec<ResolvedValue>> {
+    let config = sources.build()?;
+    let table = config.collect().map_err(|e| sources.error(e.to_string()))?;
+    let mut resolved = Vec::new();
+    for (key, value) in table {
+        flatten(sources, key, value, &mut resolved);
+    }
+    resolved.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(resolved)
+}
+
+fn flatten(
+    sources: &ConfigSources,
+    key: String,
+    value: ::config::Value,
+    resolved: &mut Vec<ResolvedValue>,
+) {
+    let source = match value.origin() {
+        Some("the environment") => format!(
+            "env {}_{}",
+            ENV_PREFIX,
+            key.replace('.', "__").to_ascii_uppercase()
+        ),
+        // File origins are rewritten relative to the working directory; map them back
+        Some(origin) => {
+            let origin_path = std::fs::canonicalize(origin).ok();
+            sources
+                .files()
+                .into_iter()
+                .find(|(_, path)| {
+                    origin_path.is_some() && std::fs::canonicalize(path).ok() == origin_path
+                })
+                .map(|(layer, path)| format!("{} file {}", layer, path.display()))
+                .unwrap_or_else(|| origin.to_string())
+        }
+        None if sources
+            .overrides
+            .iter()
+            .any(|(k, _)| k.eq_ignore_ascii_case(&key)) =>
+        {
+            "--set".to_string()
+        }
+        None => "default".to_string(),
+    };
+    match value.kind {
+        ValueKind::Table(table) => {
+            for (child_key, child) in table {
+                flatten(sources, format!("{}.{}", key, child_key), child, resolved);
+            }
+        }
+        // Cleared by a profile, e.g. the service desk name when it sets `portal_id`
+        ValueKind::Nil => {}
+        kind => {
+            let value = kind.to_string();
+            // Profiles hold credentials too, e.g. `profiles.prod.auth.token_atlassian_api`
+            let secret = SECRET_KEYS
+                .iter()
+                .any(|secret| key == *secret || key.ends_with(&format!(".{}", secret)));
+            let value = if secret && !value.is_empty() {
+                "********".to_string()
+            } else {
+                value
+            };
+            resolved.push(ResolvedValue { key, value, source });
+        }
+    }
+}
+
 /// Create a default configuration template
 pub fn create_default_config() -> JsmConfig {
     JsmConfig {
@@ -23,10 +460,289 @@ pub fn create_default_config() -> JsmConfig {
         base_url: "https://your-organization.atlassian.net".to_string(),
         portal_id: 6,
         request_type_id: 73,
+        service_desk: None,
+        request_type: None,
         auth: AuthConfig {
             username: "".to_string(),
             token_atlassian_api: "".to_string(),
             microsoft_password: "".to_string(),
+            credential_helper: None,
+            microsoft_password_helper: None,
         },
+        aliases: Default::default(),
+        timezone: None,
+        services_cache: None,
+        http: Default::default(),
+        schema_cache: Default::default(),
+        profile: None,
+        profiles: Default::default(),
+    }
+}
+
+/// Build a configuration from a customer portal URL copied from the browser, such as
+/// `https://org.atlassian.net/servicedesk/customer/portal/6/group/12/create/73`.
+/// Everything else is taken from [`create_default_config`].
+pub fn config_from_portal_url(portal_url: &str) -> Result<JsmConfig> {
+    let config_error = |message: String| JsmError::Config {
+        path: None,
+        message: format!("{} (from portal URL {})", message, portal_url),
+    };
+    let url = Url::parse(portal_url.trim()).map_err(|e| config_error(e.to_string()))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| config_error("the URL has no host".to_string()))?;
+
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(Iterator::collect)
+        .unwrap_or_default();
+    let id_after = |name: &str| -> Option<u32> {
+        let position = segments.iter().position(|segment| *segment == name)?;
+        segments.get(position + 1)?.parse().ok()
+    };
+    let portal_id = id_after("portal").ok_or_else(|| {
+        config_error("expected a path like /servicedesk/customer/portal/<id>/...".to_string())
+    })?;
+    let request_type_id = id_after("create").ok_or_else(|| {
+        config_error(
+            "the URL has no request type; open the request form and copy the URL ending in /create/<id>"
+                .to_string(),
+        )
+    })?;
+
+    let mut config = create_default_config();
+    config.org = host.split('.').next().unwrap_or(host).to_string();
+    config.base_url = match url.port() {
+        Some(port) => format!("{}://{}:{}", url.scheme(), host, port),
+        None => format!("{}://{}", url.scheme(), host),
+    };
+    config.portal_id = portal_id;
+    config.request_type_id = request_type_id;
+    Ok(config)
+}
+
+/// Build a configuration for a site given as `acme`, `acme.atlassian.net` or a full URL
+pub fn config_for_site(site: &str) -> Result<JsmConfig> {
+    let site = site.trim().trim_end_matches('/');
+    let base_url = if site.contains("://") {
+        site.to_string()
+    } else if site.contains('.') {
+        format!("https://{}", site)
+    } else {
+        format!("https://{}.atlassian.net", site)
+    };
+    let url = Url::parse(&base_url).map_err(|e| JsmError::Config {
+        path: None,
+        message: format!("invalid site '{}': {}", site, e),
+    })?;
+    let host = url.host_str().unwrap_or_default();
+
+    let mut config = create_default_config();
+    config.org = host.split('.').next().unwrap_or(host).to_string();
+    config.base_url = url.origin().ascii_serialization();
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_layers_win_and_report_their_source() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let user = dir.join("user.toml");
+        let project = dir.join("project.toml");
+        std::fs::write(
+            &user,
+            "base_url = \"https://user.atlassian.net\"\norg = \"user\"\n[auth]\nusername = \"me@example.com\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &project,
+            "base_url = \"https://acme.atlassian.net\"\nportal_id = 6\nrequest_type_id = 73\n",
+        )
+        .unwrap();
+
+        let mut sources = ConfigSources::new(&project)
+            .with_overrides(&["http.max_retries=0"])
+            .unwrap();
+        sources.system_file = None;
+        sources.user_file = Some(user.clone());
+        sources.env = Some(HashMap::from([(
+            "JSM_AUTH__TOKEN_ATLASSIAN_API".to_string(),
+            "secret".to_string(),
+        )]));
+
+        let config = load_layered(&sources).unwrap();
+        assert_eq!(config.org, "user");
+        assert_eq!(config.base_url, "https://acme.atlassian.net");
+        assert_eq!(config.auth.token_atlassian_api, "secret");
+        assert_eq!(config.http.max_retries, 0);
+        assert_eq!(config.http.timeout_secs, 60);
+
+        let resolved = resolve_with_sources(&sources).unwrap();
+        let find = |key: &str| resolved.iter().find(|value| value.key == key).unwrap();
+        assert_eq!(
+            find("base_url").source,
+            format!("project file {}", project.display())
+        );
+        assert_eq!(find("org").source, format!("user file {}", user.display()));
+        assert_eq!(find("auth.token_atlassian_api").value, "********");
+        assert_eq!(
+            find("auth.token_atlassian_api").source,
+            "env JSM_AUTH__TOKEN_ATLASSIAN_API"
+        );
+        assert_eq!(find("http.max_retries").source, "--set");
+        assert_eq!(find("http.timeout_secs").source, "default");
+    }
+
+    #[test]
+    fn profiles_override_site_target_and_credentials() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let project = dir.join("project.toml");
+        std::fs::write(
+            &project,
+            r#"org = "acme"
+base_url = "https://acme.atlassian.net"
+service_desk = "ITH"
+request_type = "Normal Change"
+
+[auth]
+username = "me@example.com"
+token_atlassian_api = "base-token"
+
+[profiles.Emergency]
+request_type_id = 74
+
+[profiles.partner]
+base_url = "https://partner.atlassian.net/"
+portal_id = 2
+request_type = "Change"
+auth = { token_atlassian_api = "partner-token" }
+
+[profiles.vault]
+auth = { credential_helper = "vault read -field=token secret/jira" }
+"#,
+        )
+        .unwrap();
+
+        let mut sources = ConfigSources::new(&project);
+        sources.system_file = None;
+        sources.user_file = None;
+        sources.env = Some(HashMap::new());
+        let config = load_layered(&sources).unwrap();
+        assert_eq!(config.profile, None);
+        assert_eq!(config.request_type.as_deref(), Some("Normal Change"));
+
+        let emergency = load_layered(
+            &sources
+                .clone()
+                .with_overrides(&["profile=Emergency"])
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(emergency.service_desk.as_deref(), Some("ITH"));
+        assert_eq!(
+            (emergency.request_type_id, emergency.request_type),
+            (74, None)
+        );
+
+        sources.env = Some(HashMap::from([(
+            "JSM_PROFILE".to_string(),
+            "partner".to_string(),
+        )]));
+        let partner = load_layered(&sources).unwrap();
+        assert_eq!(partner.profile.as_deref(), Some("partner"));
+        assert_eq!(partner.org, "partner");
+        assert_eq!(partner.base_url, "https://partner.atlassian.net");
+        assert_eq!((partner.portal_id, partner.service_desk), (2, None));
+        assert_eq!(partner.request_type.as_deref(), Some("Change"));
+        assert_eq!(partner.auth.username, "me@example.com");
+        assert_eq!(partner.auth.token_atlassian_api, "partner-token");
+
+        let resolved = resolve_with_sources(&sources).unwrap();
+        let token = resolved
+            .iter()
+            .find(|value| value.key == "profiles.partner.auth.token_atlassian_api")
+            .unwrap();
+        assert_eq!(token.value, "********");
+        let org = resolved.iter().find(|value| value.key == "org").unwrap();
+        assert_eq!(org.source, "profile partner");
+
+        // The environment and --set sit above the profile
+        sources.env = Some(HashMap::from([
+            ("JSM_PROFILE".to_string(), "partner".to_string()),
+            (
+                "JSM_AUTH__TOKEN_ATLASSIAN_API".to_string(),
+                "env-token".to_string(),
+            ),
+        ]));
+        let partner =
+            load_layered(&sources.clone().with_overrides(&["portal_id=9"]).unwrap()).unwrap();
+        assert_eq!(partner.auth.token_atlassian_api, "env-token");
+        assert_eq!((partner.portal_id, partner.service_desk), (9, None));
+        assert_eq!(partner.org, "partner");
+
+        // A profile helper clears the file token, not one from the environment
+        sources.env = Some(HashMap::from([(
+            "JSM_PROFILE".to_string(),
+            "vault".to_string(),
+        )]));
+        let vault = load_layered(&sources).unwrap();
+        assert!(vault.auth.token_atlassian_api.is_empty());
+        assert!(vault.auth.credential_helper.is_some());
+        sources.env = Some(HashMap::from([
+            ("JSM_PROFILE".to_string(), "vault".to_string()),
+            (
+                "JSM_AUTH__TOKEN_ATLASSIAN_API".to_string(),
+                "env-token".to_string(),
+            ),
+        ]));
+        assert_eq!(
+            load_layered(&sources).unwrap().auth.token_atlassian_api,
+            "env-token"
+        );
+
+        let sources = sources.with_overrides(&["profile=staging"]).unwrap();
+        let error = load_layered(&sources).unwrap_err().to_string();
+        assert!(
+            error.contains("Available profiles: emergency, partner, vault"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn parses_portal_urls() {
+        let config = config_from_portal_url(
+            "https://acme.atlassian.net/servicedesk/customer/portal/6/group/12/create/73?src=x",
+        )
+        .unwrap();
+        assert_eq!(config.org, "acme");
+        assert_eq!(config.base_url, "https://acme.atlassian.net");
+        assert_eq!((config.portal_id, config.request_type_id), (6, 73));
+
+        let config = config_from_portal_url(
+            "https://acme.atlassian.net/servicedesk/customer/portal/2/create/10",
+        )
+        .unwrap();
+        assert_eq!((config.portal_id, config.request_type_id), (2, 10));
+
+        let error =
+            config_from_portal_url("https://acme.atlassian.net/servicedesk/customer/portal/6")
+                .unwrap_err();
+        assert!(error.to_string().contains("/create/<id>"), "{}", error);
+        assert!(config_from_portal_url("not a url").is_err());
+    }
+
+    #[test]
+    fn accepts_site_names_hosts_and_urls() {
+        for site in ["acme", "acme.atlassian.net", "https://acme.atlassian.net/"] {
+            let config = config_for_site(site).unwrap();
+            assert_eq!(config.org, "acme");
+            assert_eq!(config.base_url, "https://acme.atlassian.net");
+        }
     }
 }
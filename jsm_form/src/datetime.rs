@@ -0,0 +1,408 @@
+use crate::schema::RequestTypeFields;
+use anyhow::{Context, Result};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime,
+    NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Datetime format expected by JSM date-time fields, e.g. `2025-09-23T14:00:00.000+1300`
+pub const JSM_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%z";
+
+/// Date format expected by JSM date-only fields
+pub const JSM_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Reference point and timezone used to turn dates in ticket files into JSM strings
+#[derive(Debug, Clone)]
+pub struct DateContext {
+    /// The moment `now` refers to
+    pub now: DateTime<Utc>,
+    /// Timezone for datetimes without an explicit offset, `None` for the system timezone
+    pub timezone: Option<Tz>,
+}
+
+impl DateContext {
+    /// Create a context for the current time in the given IANA timezone (e.g. `Pacific/Auckland`)
+    pub fn new(timezone: Option<&str>) -> Result<Self> {
+        let timezone = timezone
+            .map(|name| {
+                name.parse::<Tz>()
+                    .map_err(|err| anyhow::anyhow!("Invalid timezone '{}': {}", name, err))
+            })
+            .transpose()?;
+        Ok(Self {
+            now: Utc::now(),
+            timezone,
+        })
+    }
+
+    /// The current time in the configured timezone
+    fn now_local(&self) -> DateTime<FixedOffset> {
+        match self.timezone {
+            Some(tz) => self.now.with_timezone(&tz).fixed_offset(),
+            None => self.now.with_timezone(&Local).fixed_offset(),
+        }
+    }
+
+    /// Attach the configured timezone to a wall-clock time
+    fn localize(&self, naive: NaiveDateTime) -> Result<DateTime<FixedOffset>> {
+        let resolved = match self.timezone {
+            Some(tz) => pick(tz.from_local_datetime(&naive)).map(|dt| dt.fixed_offset()),
+            None => pick(Local.from_local_datetime(&naive)).map(|dt| dt.fixed_offset()),
+        };
+        resolved.with_context(|| {
+            format!(
+                "{} does not exist in timezone {} (daylight saving gap)",
+                naive,
+                self.timezone_name()
+            )
+        })
+    }
+
+    fn timezone_name(&self) -> String {
+        self.timezone
+            .map(|tz| tz.name().to_string())
+            .unwrap_or_else(|| "local".to_string())
+    }
+}
+
+fn pick<T: TimeZone>(result: LocalResult<DateTime<T>>) -> Option<DateTime<T>> {
+    match result {
+        LocalResult::Single(dt) => Some(dt),
+        // Repeated hour at the end of daylight saving: take the first occurrence
+        LocalResult::Ambiguous(earliest, _) => Some(earliest),
+        LocalResult::None => None,
+    }
+}
+
+/// Format a datetime the way JSM expects it
+pub fn format_jsm(dt: &DateTime<FixedOffset>) -> String {
+    dt.format(JSM_DATETIME_FORMAT).to_string()
+}
+
+/// Convert a native TOML datetime into a JSM date or datetime string.
+///
+/// Offset datetimes keep their offset, local datetimes use the context timezone and
+/// local dates become JSM date strings; [`resolve_date_fields`] expands those to
+/// midnight for datetime fields.
+pub fn from_toml_datetime(value: &toml::value::Datetime, ctx: &DateContext) -> Result<String> {
+    let date = value
+        .date
+        .as_ref()
+        .with_context(|| format!("TOML time '{}' has no date", value))?;
+    let date = NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())
+        .with_context(|| format!("Invalid date in '{}'", value))?;
+
+    let Some(time) = value.time.as_ref() else {
+        return Ok(date.format(JSM_DATE_FORMAT).to_string());
+    };
+    let time = NaiveTime::from_hms_nano_opt(
+        time.hour.into(),
+        time.minute.into(),
+        time.second.into(),
+        time.nanosecond,
+    )
+    .with_context(|| format!("Invalid time in '{}'", value))?;
+    let naive = date.and_time(time);
+
+    let dt = match value.offset {
+        Some(toml::value::Offset::Z) => naive.and_utc().fixed_offset(),
+        Some(toml::value::Offset::Custom { minutes }) => {
+            let offset = FixedOffset::east_opt(i32::from(minutes) * 60)
+                .with_context(|| format!("Invalid offset in '{}'", value))?;
+            pick(offset.from_local_datetime(&naive))
+                .with_context(|| format!("Invalid datetime '{}'", value))?
+        }
+        None => ctx.localize(naive)?,
+    };
+    Ok(format_jsm(&dt))
+}
+
+/// Convert a relative time expression into a JSM datetime string.
+///
+/// Supported forms (case-insensitive):
+/// - `now`, `now+2h`, `now - 30m`, `now+1d+4h` (units: m, h, d, w)
+/// - `today 14:00`, `tomorrow 09:30`, `yesterday`
+/// - `next tue 09:00`, `next friday`
+///
+/// Returns `Ok(None)` if the text is not a time expression, so ordinary strings pass through.
+pub fn parse_relative(expr: &str, ctx: &DateContext) -> Result<Option<String>> {
+    Ok(relative_datetime(expr, ctx)?.map(|dt| format_jsm(&dt)))
+}
+
+/// The moment a relative time expression refers to, see [`parse_relative`]
+fn relative_datetime(expr: &str, ctx: &DateContext) -> Result<Option<DateTime<FixedOffset>>> {
+    let text = expr.trim().to_ascii_lowercase();
+    let now = ctx.now_local();
+
+    if let Some(rest) = text.strip_prefix("now") {
+        let Some(offset) = parse_offsets(rest)? else {
+            return Ok(None);
+        };
+        let dt = now
+            .checked_add_signed(offset)
+            .with_context(|| format!("'{}' is out of range", expr.trim()))?;
+        return Ok(Some(dt));
+    }
+
+    let mut words = text.split_whitespace();
+    let (date, time_word) = match words.next() {
+        Some("today") => (now.date_naive(), words.next()),
+        Some("tomorrow") => (now.date_naive() + Duration::days(1), words.next()),
+        Some("yesterday") => (now.date_naive() - Duration::days(1), words.next()),
+        Some("next") => {
+            let Some(weekday) = words.next().and_then(parse_weekday) else {
+                return Ok(None);
+            };
+            (next_weekday(now.date_naive(), weekday), words.next())
+        }
+        _ => return Ok(None),
+    };
+    if words.next().is_some() {
+        return Ok(None);
+    }
+
+    let time = match time_word {
+        Some(word) => match NaiveTime::parse_from_str(word, "%H:%M") {
+            Ok(time) => time,
+            Err(_) => return Ok(None),
+        },
+        None => NaiveTime::MIN,
+    };
+
+    Ok(Some(ctx.localize(date.and_time(time))?))
+}
+
+/// Expand relative time expressions in the `date` and `datetime` fields of the request
+/// type; other fields are left alone. Date fields get `yyyy-MM-dd`, so a datetime given
+/// for one (relative or written out) is cut to its date. A plain date given for a
+/// datetime field becomes midnight in the context timezone.
+pub fn resolve_date_fields(
+    fields: &mut BTreeMap<String, Value>,
+    schema: &RequestTypeFields,
+    ctx: &DateContext,
+) -> Result<()> {
+    for field in &schema.request_type_fields {
+        let date_only = match field.jira_schema.field_type.as_str() {
+            "date" => true,
+            "datetime" => false,
+            _ => continue,
+        };
+        let Some(Value::String(text)) = fields.get_mut(&field.field_id) else {
+            continue;
+        };
+        let dt = match relative_datetime(text, ctx)? {
+            Some(dt) => dt,
+            None if date_only => match DateTime::parse_from_str(text.trim(), JSM_DATETIME_FORMAT) {
+                Ok(dt) => dt,
+                Err(_) => continue,
+            },
+            None => match NaiveDate::parse_from_str(text.trim(), JSM_DATE_FORMAT) {
+                Ok(date) => ctx.localize(date.and_time(NaiveTime::MIN))?,
+                Err(_) => continue,
+            },
+        };
+        *text = if date_only {
+            dt.format(JSM_DATE_FORMAT).to_string()
+        } else {
+            format_jsm(&dt)
+        };
+    }
+    Ok(())
+}
+
+/// Parse a sequence of `+N<unit>` / `-N<unit>` terms. Returns `Ok(None)` if the text is
+/// not such a sequence, and an error if the offset is out of range.
+fn parse_offsets(text: &str) -> Result<Option<Duration>> {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut rest = compact.as_str();
+    let mut total = Duration::zero();
+    let out_of_range = || anyhow::anyhow!("Time offset 'now{}' is out of range", compact);
+
+    while let Some(first) = rest.chars().next() {
+        let sign = match first {
+            '+' => 1,
+            '-' => -1,
+            _ => return Ok(None),
+        };
+        rest = &rest[1..];
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        if digits == 0 {
+            return Ok(None);
+        }
+        let amount: i64 = rest[..digits].parse().map_err(|_| out_of_range())?;
+        rest = &rest[digits..];
+        let unit_len = rest.chars().take_while(char::is_ascii_alphabetic).count();
+        let unit = match &rest[..unit_len] {
+            "m" | "min" | "mins" | "minute" | "minutes" => Duration::try_minutes(amount),
+            "h" | "hr" | "hrs" | "hour" | "hours" => Duration::try_hours(amount),
+            "d" | "day" | "days" => Duration::try_days(amount),
+            "w" | "week" | "weeks" => Duration::try_weeks(amount),
+            _ => return Ok(None),
+        };
+        rest = &rest[unit_len..];
+        total = unit
+            .and_then(|unit| unit.checked_mul(sign))
+            .and_then(|unit| total.checked_add(&unit))
+            .ok_or_else(out_of_range)?;
+    }
+
+    Ok(Some(total))
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// The next date falling on `weekday`, always strictly after `from`
+fn next_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (7 + weekday.num_days_from_monday() as i64
+        - from.weekday().num_days_from_monday() as i64)
+        % 7;
+    from + Duration::days(if ahead == 0 { 7 } else { ahead })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 2025-09-17 09:15 in Auckland (+12:00)
+    fn ctx() -> DateContext {
+        DateContext {
+            now: "2025-09-16T21:15:00Z".parse().unwrap(),
+            timezone: Some(chrono_tz::Pacific::Auckland),
+        }
+    }
+
+    fn toml_datetime(text: &str) -> toml::value::Datetime {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn converts_native_toml_datetimes() {
+        let ctx = ctx();
+        assert_eq!(
+            from_toml_datetime(&toml_datetime("2025-09-23T14:00:00+13:00"), &ctx).unwrap(),
+            "2025-09-23T14:00:00.000+1300"
+        );
+        assert_eq!(
+            from_toml_datetime(&toml_datetime("2025-09-23T01:00:00Z"), &ctx).unwrap(),
+            "2025-09-23T01:00:00.000+0000"
+        );
+        // Local datetime picks up the configured timezone (NZDT from 28 September)
+        assert_eq!(
+            from_toml_datetime(&toml_datetime("2025-10-01T14:00:00"), &ctx).unwrap(),
+            "2025-10-01T14:00:00.000+1300"
+        );
+        assert_eq!(
+            from_toml_datetime(&toml_datetime("2025-09-23"), &ctx).unwrap(),
+            "2025-09-23"
+        );
+        assert!(from_toml_datetime(&toml_datetime("14:00:00"), &ctx).is_err());
+    }
+
+    #[test]
+    fn converts_relative_expressions() {
+        let ctx = ctx();
+        let parse = |expr: &str| parse_relative(expr, &ctx).unwrap();
+        assert_eq!(parse("now").unwrap(), "2025-09-17T09:15:00.000+1200");
+        assert_eq!(parse("now+2h").unwrap(), "2025-09-17T11:15:00.000+1200");
+        assert_eq!(
+            parse("NOW + 1d - 15m").unwrap(),
+            "2025-09-18T09:00:00.000+1200"
+        );
+        assert_eq!(
+            parse("tomorrow 14:00").unwrap(),
+            "2025-09-18T14:00:00.000+1200"
+        );
+        assert_eq!(parse("today").unwrap(), "2025-09-17T00:00:00.000+1200");
+        assert_eq!(
+            parse("next tue 09:00").unwrap(),
+            "2025-09-23T09:00:00.000+1200"
+        );
+        assert_eq!(
+            parse("next wednesday").unwrap(),
+            "2025-09-24T00:00:00.000+1200"
+        );
+    }
+
+    #[test]
+    fn leaves_ordinary_text_alone() {
+        let ctx = ctx();
+        for text in [
+            "Deploy new version",
+            "nowhere",
+            "now and then",
+            "today we deploy",
+            "next release",
+            "tomorrow 25:00",
+            "now+",
+            "now+2x",
+        ] {
+            assert_eq!(parse_relative(text, &ctx).unwrap(), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_offsets() {
+        let ctx = ctx();
+        for text in [
+            "now+9999999999999w",
+            "now+99999999999999999999m",
+            "now+200000000d",
+            "now-9223372036854775807m-9223372036854775807m",
+        ] {
+            let error = parse_relative(text, &ctx).unwrap_err().to_string();
+            assert!(error.contains("out of range"), "{text}: {error}");
+        }
+    }
+
+    #[test]
+    fn expands_expressions_only_in_date_fields() {
+        let schema: RequestTypeFields = serde_json::from_value(serde_json::json!({
+            "requestTypeFields": [
+                { "fieldId": "summary", "name": "Summary", "jiraSchema": { "type": "string" } },
+                { "fieldId": "duedate", "name": "Due date", "jiraSchema": { "type": "date" } },
+                { "fieldId": "customfield_10878", "name": "Planned start", "jiraSchema": { "type": "datetime" } },
+                { "fieldId": "customfield_10879", "name": "Review date", "jiraSchema": { "type": "date" } },
+                { "fieldId": "customfield_10880", "name": "Planned end", "jiraSchema": { "type": "datetime" } }
+            ]
+        }))
+        .unwrap();
+        let mut fields = BTreeMap::from([
+            ("summary".to_string(), Value::from("now")),
+            ("description".to_string(), Value::from("today")),
+            ("duedate".to_string(), Value::from("tomorrow")),
+            ("customfield_10878".to_string(), Value::from("now+2h")),
+            (
+                "customfield_10879".to_string(),
+                Value::from("2025-09-23T14:00:00.000+1300"),
+            ),
+            ("customfield_10880".to_string(), Value::from("2025-09-24")),
+        ]);
+        resolve_date_fields(&mut fields, &schema, &ctx()).unwrap();
+        assert_eq!(fields["summary"], "now");
+        assert_eq!(fields["description"], "today");
+        assert_eq!(fields["duedate"], "2025-09-18");
+        assert_eq!(fields["customfield_10878"], "2025-09-17T11:15:00.000+1200");
+        assert_eq!(fields["customfield_10879"], "2025-09-23");
+        assert_eq!(fields["customfield_10880"], "2025-09-24T00:00:00.000+1200");
+    }
+
+    #[test]
+    fn rejects_unknown_timezones() {
+        assert!(DateContext::new(Some("Pacific/Auckland")).is_ok());
+        assert!(DateContext::new(Some("Mars/Olympus")).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use crate::JsmConfig;
+use crate::error::JsmError;
+use crate::schema::RequestType;
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Page size requested from the paginated service desk endpoints
+const PAGE_SIZE: u32 = 50;
+
+/// One page of a paginated service desk API collection
+#[derive(Debug, Deserialize)]
+pub struct PagedResponse<T> {
+    #[serde(default)]
+    pub size: u32,
+    #[serde(default)]
+    pub start: u32,
+    #[serde(default)]
+    pub limit: u32,
+    #[serde(rename = "isLastPage", default = "default_last_page")]
+    pub is_last_page: bool,
+    #[serde(default = "Vec::new")]
+    pub values: Vec<T>,
+}
+
+fn default_last_page() -> bool {
+    true
+}
+
+/// A service desk as returned by `/rest/servicedeskapi/servicedesk`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ServiceDesk {
+    pub id: String,
+    #[serde(rename = "projectId", default)]
+    pub project_id: String,
+    #[serde(rename = "projectName", default)]
+    pub project_name: String,
+    #[serde(rename = "projectKey", default)]
+    pub project_key: String,
+}
+
+/// Fetch every page of a service desk API collection
+async fn fetch_all<T: DeserializeOwned>(
+    client: &Client,
+    config: &JsmConfig,
+    url: &str,
+) -> Result<Vec<T>> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    loop {
+        let response = crate::http::send(
+            &config.http,
+            client
+                .get(url)
+                .query(&[("start", start), ("limit", PAGE_SIZE)])
+                .basic_auth(
+                    &config.auth.username,
+                    Some(&config.auth.token_atlassian_api),
+                )
+                .header("Accept", "application/json"),
+        )
+        .await
+        .with_context(|| format!("Failed to fetch {}", url))?;
+
+        if !response.status().is_success() {
+            return Err(JsmError::from_response(response).await.into());
+        }
+        let page: PagedResponse<T> = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse response from {}", url))?;
+
+        let count = page.values.len() as u32;
+        items.extend(page.values);
+        if page.is_last_page || count == 0 {
+            return Ok(items);
+        }
+        start += count;
+    }
+}
+
+/// List the service desks visible to the configured user
+pub async fn list_service_desks(client: &Client, config: &JsmConfig) -> Result<Vec<ServiceDesk>> {
+    let url = format!("{}/rest/servicedeskapi/servicedesk", config.base_url);
+    fetch_all(client, config, &url).await
+}
+
+/// List the request types of a service desk
+pub async fn list_request_types(
+    client: &Client,
+    config: &JsmConfig,
+    service_desk_id: &str,
+) -> Result<Vec<RequestType>> {
+    let url = format!(
+        "{}/rest/servicedeskapi/servicedesk/{}/requesttype",
+        config.base_url, service_desk_id
+    );
+    fetch_all(client, config, &url).await
+}
+
+/// Find a service desk by ID, project key or project name (case-insensitive)
+pub fn find_service_desk<'a>(
+    wanted: &str,
+    desks: &'a [ServiceDesk],
+) -> std::result::Result<&'a ServiceDesk, String> {
+    let wanted = wanted.trim();
+    desks
+        .iter()
+        .find(|desk| desk.id == wanted || desk.project_key.eq_ignore_ascii_case(wanted))
+        .or_else(|| {
+            desks
+                .iter()
+                .find(|desk| desk.project_name.eq_ignore_ascii_case(wanted))
+        })
+        .ok_or_else(|| {
+            format!(
+                "unknown service desk '{}'. Available service desks: {}",
+                wanted,
+                desks
+                    .iter()
+                    .map(|desk| format!("{} ({})", desk.project_key, desk.project_name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+/// Find a request type by ID or name (case-insensitive)
+pub fn find_request_type<'a>(
+    wanted: &str,
+    request_types: &'a [RequestType],
+) -> std::result::Result<&'a RequestType, String> {
+    let wanted = wanted.trim();
+    let matches: Vec<&RequestType> = request_types
+        .iter()
+        .filter(|request_type| {
+            request_type.id == wanted || request_type.name.trim().eq_ignore_ascii_case(wanted)
+        })
+        .collect();
+
+    match matches.as_slice() {
+        [request_type] => Ok(request_type),
+        [] => Err(format!(
+            "unknown request type '{}'. Available request types: {}",
+            wanted,
+            request_types
+                .iter()
+                .map(|request_type| request_type.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        several => Err(format!(
+            "request type name '{}' is ambiguous, it matches IDs {}; set request_type_id instead",
+            wanted,
+            several
+                .iter()
+                .map(|request_type| request_type.id.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Resolve `service_desk` and `request_type` names in the config to `portal_id` and
+/// `request_type_id`. IDs set alongside a name must agree with it.
+pub async fn resolve_target(client: &Client, config: &mut JsmConfig) -> Result<()> {
+    if let Some(wanted) = config.service_desk.clone() {
+        let desks = list_service_desks(client, config).await?;
+        let desk = find_service_desk(&wanted, &desks).map_err(config_error)?;
+        let id: u32 = desk
+            .id
+            .parse()
+            .with_context(|| format!("Unexpected service desk ID '{}'", desk.id))?;
+        if config.portal_id != 0 && config.portal_id != id {
+            return Err(config_error(format!(
+                "service_desk '{}' is service desk {}, but portal_id = {}; remove one of them",
+                wanted, id, config.portal_id
+            )));
+        }
+        crate::log_debug!("Resolved service desk '{}' to {}", wanted, id);
+        config.portal_id = id;
+    }
+
+    if let Some(wanted) = config.request_type.clone() {
+        if config.portal_id == 0 {
+            return Err(config_error(
+                "request_type is set by name, but neither service_desk nor portal_id is set"
+                    .to_string(),
+            ));
+        }
+        let request_types =
+            list_request_types(client, config, &config.portal_id.to_string()).await?;
+        let request_type = find_request_type(&wanted, &request_types).map_err(config_error)?;
+        let id: u32 = request_type
+            .id
+            .parse()
+            .with_context(|| format!("Unexpected request type ID '{}'", request_type.id))?;
+        if config.request_type_id != 0 && config.request_type_id != id {
+            return Err(config_error(format!(
+                "request_type '{}' is request type {}, but request_type_id = {}; remove one of them",
+                wanted, id, config.request_type_id
+            )));
+        }
+        crate::log_debug!("Resolved request type '{}' to {}", wanted, id);
+        config.request_type_id = id;
+    }
+
+    require_target(config)
+}
+
+/// Check that the target IDs are set, so no call goes out for service desk or request
+/// type 0 when names were given but [`resolve_target`] has not run
+pub fn require_target(config: &JsmConfig) -> Result<()> {
+    if config.portal_id != 0 && config.request_type_id != 0 {
+        return Ok(());
+    }
+    Err(config_error(
+        if config.service_desk.is_some() || config.request_type.is_some() {
+            "service_desk/request_type names have not been resolved to IDs; call resolve_target first"
+        } else {
+            "set portal_id/request_type_id, or service_desk/request_type by name"
+        }
+        .to_string(),
+    ))
+}
+
+fn config_error(message: String) -> anyhow::Error {
+    JsmError::Config {
+        path: None,
+        message,
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_paged_responses() {
+        let page: PagedResponse<ServiceDesk> = serde_json::from_value(json!({
+            "size": 1, "start": 0, "limit": 50, "isLastPage": false,
+            "values": [
+                { "id": "6", "projectId": "10010", "projectName": "IT Help", "projectKey": "ITH" }
+            ],
+            "_links": {}
+        }))
+        .unwrap();
+        assert!(!page.is_last_page);
+        assert_eq!(page.values[0].project_key, "ITH");
+    }
+
+    #[tokio::test]
+    async fn unresolved_names_fail_before_any_request() {
+        let mut config = crate::config::create_default_config();
+        // Nothing listens on port 1: the check must fail before anything is sent
+        config.base_url = "http://127.0.0.1:1".to_string();
+        config.portal_id = 0;
+        config.service_desk = Some("ITH".to_string());
+
+        let error = crate::schema::fetch_request_type_fields(&Client::new(), &config)
+            .await
+            .unwrap_err();
+        let error = error.downcast_ref::<JsmError>().unwrap();
+        assert_eq!(error.exit_code(), 3);
+        assert!(error.to_string().contains("resolve_target"), "{}", error);
+
+        let form_data = crate::FormData::new(std::collections::HashMap::from([(
+            "summary".to_string(),
+            json!("Deploy"),
+        )]));
+        let error = crate::form::create_request(&Client::new(), &config, form_data)
+            .await
+            .unwrap_err();
+        assert_eq!(error.exit_code(), 3);
+    }
+
+    #[test]
+    fn finds_desks_and_request_types_by_name() {
+        let desks: Vec<ServiceDesk> = serde_json::from_value(json!([
+            { "id": "6", "projectName": "IT Help", "projectKey": "ITH" },
+            { "id": "7", "projectName": "HR", "projectKey": "HR" }
+        ]))
+        .unwrap();
+        assert_eq!(find_service_desk("ith", &desks).unwrap().id, "6");
+        assert_eq!(find_service_desk("7", &desks).unwrap().id, "7");
+        assert_eq!(find_service_desk("IT Help", &desks).unwrap().id, "6");
+        assert!(
+            find_service_desk("OPS", &desks)
+                .unwrap_err()
+                .contains("ITH (IT Help)")
+        );
+
+        let request_types: Vec<RequestType> = serde_json::from_value(json!([
+            { "id": "73", "name": "Normal Change" },
+            { "id": "74", "name": "Emergency Change" }
+        ]))
+        .unwrap();
+        assert_eq!(
+            find_request_type("normal change", &request_types)
+                .unwrap()
+                .id,
+            "73"
+        );
+        assert!(
+            find_request_type("Standard Change", &request_types)
+                .unwrap_err()
+                .contains("Normal Change, Emergency Change")
+        );
+    }
+}
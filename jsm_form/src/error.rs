@@ -1 +1,358 @@
-pub type JsmError = anyhow::Error;
+use crate::schema::{FieldIssue, IssueKind, RequestTypeFields};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Result type of the library entry points that report a [`JsmError`]
+pub type Result<T> = std::result::Result<T, JsmError>;
+
+/// Errors returned by the library
+#[derive(Debug, thiserror::Error)]
+pub enum JsmError {
+    /// The credentials were rejected (401)
+    #[error("Authentication failed (status {status}) for {url}: {message}")]
+    Auth {
+        status: u16,
+        url: String,
+        message: String,
+    },
+    /// The credentials are valid but lack permission (403)
+    #[error("Access denied (status {status}) for {url}: {message}")]
+    Forbidden {
+        status: u16,
+        url: String,
+        message: String,
+    },
+    /// Any other non-success response from the JSM REST API
+    #[error("JSM API request to {url} failed with status {status}:{}", api_details(.error_messages, .errors, .body))]
+    Api {
+        status: u16,
+        url: String,
+        /// `errorMessages` (and `errorMessage`) from the response body
+        error_messages: Vec<String>,
+        /// Per-field `errors` from the response body. Field names are filled in by
+        /// [`JsmError::with_field_names`].
+        errors: Vec<FieldIssue>,
+        /// The raw response body
+        body: String,
+    },
+    /// Ticket fields do not match the request type; nothing was sent
+    #[error("Ticket fields failed validation against request type {request_type_id} ({} problem(s)):\n{}", .issues.len(), list_issues(.issues))]
+    Validation {
+        request_type_id: u32,
+        issues: Vec<FieldIssue>,
+    },
+    /// The configuration is missing or invalid
+    #[error("Invalid configuration{}: {message}", .path.as_ref().map(|path| format!(" {}", path.display())).unwrap_or_default())]
+    Config {
+        /// The config file, when the problem is tied to one
+        path: Option<PathBuf>,
+        message: String,
+    },
+    /// The request could not be sent or its response could not be read
+    #[error("Request to {url} failed: {source}")]
+    Network {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    /// The server did not answer, or a resource did not become available, in time
+    #[error("Timed out waiting for {url}")]
+    Timeout { url: String },
+    /// Browser automation failed, e.g. a selector did not match the page
+    #[error("Browser automation failed on {url}: {source:#}")]
+    Browser {
+        /// The page the browser was on when the step failed
+        url: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    /// Anything not covered by a more specific variant. Context added to a typed error
+    /// is kept here too; the exit code, status and field issues are those of the
+    /// typed error inside.
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+/// Error body returned by the Jira and service desk REST APIs
+#[derive(Debug, Default, Deserialize)]
+struct ApiErrorBody {
+    #[serde(rename = "errorMessages", default)]
+    error_messages: Vec<String>,
+    #[serde(rename = "errorMessage", default)]
+    error_message: Option<String>,
+    #[serde(default)]
+    errors: BTreeMap<String, String>,
+}
+
+impl JsmError {
+    /// Build the error for a non-success response, consuming its body
+    pub async fn from_response(response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let url = response.url().to_string();
+        let body = response.text().await.unwrap_or_default();
+        Self::from_status(status, url, body)
+    }
+
+    /// Build the error for a non-success status and response body
+    pub fn from_status(status: u16, url: String, body: String) -> Self {
+        let parsed: ApiErrorBody = serde_json::from_str(&body).unwrap_or_default();
+        let mut error_messages = parsed.error_messages;
+        error_messages.extend(parsed.error_message);
+
+        let errors: Vec<FieldIssue> = parsed
+            .errors
+            .into_iter()
+            .map(|(field_id, message)| FieldIssue {
+                field_id,
+                field_name: None,
+                kind: IssueKind::Other,
+                message,
+            })
+            .collect();
+
+        match status {
+            401 => JsmError::Auth {
+                status,
+                url,
+                message: api_details(&error_messages, &errors, &body)
+                    .trim_start()
+                    .to_string(),
+            },
+            403 => JsmError::Forbidden {
+                status,
+                url,
+                message: api_details(&error_messages, &errors, &body)
+                    .trim_start()
+                    .to_string(),
+            },
+            _ => JsmError::Api {
+                status,
+                url,
+                error_messages,
+                errors,
+                body,
+            },
+        }
+    }
+
+    /// Fill in the display names of the fields in an API error from the request type
+    pub fn with_field_names(mut self, schema: &RequestTypeFields) -> Self {
+        if let JsmError::Api { errors, .. } = &mut self {
+            for error in errors {
+                error.field_name = schema
+                    .field(&error.field_id)
+                    .map(|field| field.name.clone());
+            }
+        }
+        self
+    }
+
+    /// Field-level problems: the pre-submit validation issues, or the per-field
+    /// `errors` JSM returned
+    pub fn field_issues(&self) -> &[FieldIssue] {
+        match self {
+            JsmError::Validation { issues, .. } => issues,
+            JsmError::Api { errors, .. } => errors,
+            JsmError::Other(_) => self.typed_cause().map_or(&[], JsmError::field_issues),
+            _ => &[],
+        }
+    }
+
+    /// The typed error wrapped in an [`JsmError::Other`] with added context, if any
+    fn typed_cause(&self) -> Option<&JsmError> {
+        match self {
+            JsmError::Other(error) => error
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<JsmError>()),
+            _ => None,
+        }
+    }
+
+    /// Classify a transport error from reqwest
+    pub fn network(url: impl Into<String>, source: reqwest::Error) -> Self {
+        let url = url.into();
+        if source.is_timeout() {
+            JsmError::Timeout { url }
+        } else {
+            JsmError::Network { url, source }
+        }
+    }
+
+    /// HTTP status of the failed response, if there was one
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            JsmError::Auth { status, .. }
+            | JsmError::Forbidden { status, .. }
+            | JsmError::Api { status, .. } => Some(*status),
+            JsmError::Other(_) => self.typed_cause().and_then(JsmError::status),
+            _ => None,
+        }
+    }
+
+    /// Process exit code the CLI uses for this error:
+    ///
+    /// | Code | Meaning |
+    /// |------|---------|
+    /// | 1 | Other error |
+    /// | 3 | Configuration error |
+    /// | 4 | Authentication failed (401) |
+    /// | 5 | Access denied (403) |
+    /// | 6 | Ticket fields failed validation |
+    /// | 7 | JSM API error |
+    /// | 8 | Network error or timeout |
+    /// | 9 | Browser automation failed |
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            JsmError::Other(_) => self.typed_cause().map_or(1, JsmError::exit_code),
+            JsmError::Config { .. } => 3,
+            JsmError::Auth { .. } => 4,
+            JsmError::Forbidden { .. } => 5,
+            JsmError::Validation { .. } => 6,
+            JsmError::Api { .. } => 7,
+            JsmError::Network { .. } | JsmError::Timeout { .. } => 8,
+            JsmError::Browser { .. } => 9,
+        }
+    }
+}
+
+impl From<anyhow::Error> for JsmError {
+    /// Unwrap a typed error that has no context added; otherwise keep the whole chain,
+    /// since a downcast would drop the context messages
+    fn from(error: anyhow::Error) -> Self {
+        if error
+            .chain()
+            .next()
+            .is_some_and(|outer| outer.is::<JsmError>())
+        {
+            match error.downcast::<JsmError>() {
+                Ok(error) => return error,
+                Err(error) => return JsmError::Other(error),
+            }
+        }
+        JsmError::Other(error)
+    }
+}
+
+/// `errorMessages` on one line, followed by a table of the per-field errors.
+/// Falls back to the raw body when it could not be parsed.
+fn api_details(error_messages: &[String], errors: &[FieldIssue], body: &str) -> String {
+    if error_messages.is_empty() && errors.is_empty() {
+        return format!(" {}", body);
+    }
+
+    let mut details = String::new();
+    if !error_messages.is_empty() {
+        details = format!(" {}", error_messages.join("; "));
+    }
+    if !errors.is_empty() {
+        let rows: Vec<Vec<String>> = errors
+            .iter()
+            .map(|error| {
+                vec![
+                    error.field_id.clone(),
+                    error.field_name.clone().unwrap_or_else(|| "-".to_string()),
+                    error.message.clone(),
+                ]
+            })
+            .collect();
+        details.push('\n');
+        details.push_str(&crate::table::render(
+            &["Field ID", "Field name", "Message"],
+            &rows,
+        ));
+    }
+    details
+}
+
+fn list_issues(issues: &[FieldIssue]) -> String {
+    issues
+        .iter()
+        .map(|issue| format!("  - {}", issue))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_api_error_bodies() {
+        let body =
+            r#"{"errorMessages":["Bad request"],"errors":{"customfield_10878":"Invalid date"}}"#;
+        let error = JsmError::from_status(400, "https://x/rest".to_string(), body.to_string());
+        match &error {
+            JsmError::Api { error_messages, .. } => {
+                assert_eq!(error_messages, &vec!["Bad request".to_string()]);
+            }
+            other => panic!("expected Api, got {:?}", other),
+        }
+        assert_eq!(error.status(), Some(400));
+        assert_eq!(error.field_issues()[0].field_id, "customfield_10878");
+        assert_eq!(error.field_issues()[0].field_name, None);
+
+        let servicedesk = r#"{"errorMessage":"Request type not found","i18nErrorMessage":{}}"#;
+        let error = JsmError::from_status(404, String::new(), servicedesk.to_string());
+        assert!(error.to_string().ends_with("Request type not found"));
+
+        assert!(matches!(
+            JsmError::from_status(401, String::new(), "nope".to_string()),
+            JsmError::Auth { .. }
+        ));
+        assert!(matches!(
+            JsmError::from_status(403, String::new(), String::new()),
+            JsmError::Forbidden { .. }
+        ));
+    }
+
+    #[test]
+    fn names_api_field_errors_from_the_schema() {
+        let schema: RequestTypeFields = serde_json::from_value(serde_json::json!({
+            "requestTypeFields": [
+                { "fieldId": "customfield_10878", "name": "Planned start" }
+            ]
+        }))
+        .unwrap();
+        let body = r#"{"errorMessages":[],"errors":{"customfield_10878":"Invalid date","customfield_99":"Unknown"}}"#;
+        let error = JsmError::from_status(
+            400,
+            "https://x/rest/servicedeskapi/request".to_string(),
+            body.to_string(),
+        )
+        .with_field_names(&schema);
+
+        assert_eq!(
+            error.field_issues()[0].field_name.as_deref(),
+            Some("Planned start")
+        );
+        assert_eq!(
+            error.to_string(),
+            "JSM API request to https://x/rest/servicedeskapi/request failed with status 400:\n\
+             Field ID           Field name     Message\n\
+             -----------------  -------------  ------------\n\
+             customfield_10878  Planned start  Invalid date\n\
+             customfield_99     -              Unknown"
+        );
+    }
+
+    #[test]
+    fn keeps_typed_error_through_anyhow() {
+        let typed = JsmError::Timeout {
+            url: "https://x".to_string(),
+        };
+        let wrapped = anyhow::Error::from(typed);
+        let error = JsmError::from(wrapped);
+        assert_eq!(error.exit_code(), 8);
+
+        let error = JsmError::from(anyhow::anyhow!("something else"));
+        assert_eq!(error.exit_code(), 1);
+
+        let typed = JsmError::from_status(401, "https://x".to_string(), String::new());
+        let error = JsmError::from(anyhow::Error::from(typed).context("Request ITH-1 was created"));
+        assert!(matches!(error, JsmError::Other(_)));
+        assert!(format!("{:#}", error).starts_with("Request ITH-1 was created: "));
+        assert_eq!(error.exit_code(), 4);
+        assert_eq!(error.status(), Some(401));
+    }
+}
@@ -1,104 +1,415 @@
+use crate::error::JsmError;
+use crate::schema::{FieldIssue, IssueKind};
 use crate::{FormData, JsmConfig};
 use anyhow::{Context, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 /// Remove any keys that are known to be configuration-only or not valid for the JSM REST API.
 fn sanitize_request_fields(
     mut fields: std::collections::HashMap<String, serde_json::Value>,
 ) -> std::collections::HashMap<String, serde_json::Value> {
     // Keys we know should not be sent to the API
-    const CONFIG_KEYS: [&str; 1] = ["risk_assessment"]; // extend as needed
+    const CONFIG_KEYS: [&str; 4] = [
+        "risk_assessment",
+        "attachments",
+        "raise_on_behalf_of",
+        "request_participants",
+    ]; // extend as needed
     for k in CONFIG_KEYS {
         fields.remove(k);
     }
     fields
 }
 
-/// Submit form data to the JSM service desk using the REST API
-pub async fn submit_form(client: &Client, config: &JsmConfig, form_data: FormData) -> Result<()> {
-    // Use the Atlassian Service Desk REST API to create a customer request
-    let create_request_url = format!("{}/rest/servicedeskapi/request", config.base_url);
-
+/// Build the payload from the ticket data: aliases replaced by field IDs and the
+/// idempotency marker added. Dates, option labels, services and people are still as
+/// written; `submit_form` resolves them against the request type before sending.
+pub fn build_payload(config: &JsmConfig, form_data: FormData) -> Result<CreateRequestPayload> {
     // Prepare the request payload according to Atlassian API format
     let cleaned_fields = sanitize_request_fields(form_data.fields);
-    let request_payload = CreateRequestPayload {
+    let mut resolved_fields = crate::aliases::resolve_aliases(cleaned_fields, &config.aliases)?;
+    if let (Some(key), Some(serde_json::Value::String(description))) = (
+        &form_data.idempotency_key,
+        resolved_fields.get_mut("description"),
+    ) {
+        *description = crate::idempotency::mark_description(description, key);
+    }
+    Ok(CreateRequestPayload {
         service_desk_id: config.portal_id,
         request_type_id: config.request_type_id,
-        request_field_values: cleaned_fields,
-        raise_on_behalf_of: None, // Current user
+        request_field_values: resolved_fields.into_iter().collect(),
+        // None raises the request as the current user
+        raise_on_behalf_of: form_data.raise_on_behalf_of,
+        request_participants: form_data.request_participants,
+    })
+}
+
+/// The resolution steps that need only the request type schema: relative dates in date
+/// fields and option labels. Returns the values that match no option.
+pub fn resolve_offline(
+    config: &JsmConfig,
+    payload: &mut CreateRequestPayload,
+    schema: &crate::schema::RequestTypeFields,
+) -> Result<Vec<crate::schema::FieldIssue>> {
+    crate::datetime::resolve_date_fields(
+        &mut payload.request_field_values,
+        schema,
+        &crate::datetime::DateContext::new(config.timezone.as_deref())?,
+    )?;
+    Ok(crate::options::resolve_option_values(
+        &mut payload.request_field_values,
+        schema,
+    ))
+}
+
+/// Reporter and participants the request type does not allow
+pub fn people_issues(
+    payload: &CreateRequestPayload,
+    schema: &crate::schema::RequestTypeFields,
+) -> Vec<FieldIssue> {
+    let mut issues = Vec::new();
+    if payload.raise_on_behalf_of.is_some() && !schema.can_raise_on_behalf_of {
+        issues.push(FieldIssue {
+            field_id: "raise_on_behalf_of".to_string(),
+            field_name: None,
+            kind: IssueKind::NotAllowed,
+            message: "the request type does not allow raising requests on behalf of others"
+                .to_string(),
+        });
+    }
+    if !payload.request_participants.is_empty() && !schema.can_add_request_participants {
+        issues.push(FieldIssue {
+            field_id: "request_participants".to_string(),
+            field_name: None,
+            kind: IssueKind::NotAllowed,
+            message: "the request type does not allow adding request participants".to_string(),
+        });
+    }
+    issues
+}
+
+/// Replace email addresses of the reporter and participants with account IDs. Returns
+/// the settings the request type does not allow and the addresses that match no account.
+async fn resolve_people(
+    client: &Client,
+    config: &JsmConfig,
+    payload: &mut CreateRequestPayload,
+    schema: &crate::schema::RequestTypeFields,
+) -> Result<Vec<FieldIssue>> {
+    let mut issues = people_issues(payload, schema);
+    if schema.can_raise_on_behalf_of
+        && let Some(reporter) = payload.raise_on_behalf_of.as_mut()
+    {
+        resolve_person(client, config, "raise_on_behalf_of", reporter, &mut issues).await?;
+    }
+    if schema.can_add_request_participants {
+        for participant in payload.request_participants.iter_mut() {
+            resolve_person(
+                client,
+                config,
+                "request_participants",
+                participant,
+                &mut issues,
+            )
+            .await?;
+        }
+    }
+    Ok(issues)
+}
+
+/// Replace an email address with the account ID showing it, or report why there is none
+async fn resolve_person(
+    client: &Client,
+    config: &JsmConfig,
+    field_id: &str,
+    person: &mut String,
+    issues: &mut Vec<FieldIssue>,
+) -> Result<()> {
+    if !crate::users::is_email(person) {
+        return Ok(());
+    }
+    let users = crate::users::search_users(client, config, person).await?;
+    match crate::users::pick_account(person, &users) {
+        Ok(account) => *person = account.account_id.clone(),
+        Err(message) => issues.push(FieldIssue {
+            field_id: field_id.to_string(),
+            field_name: None,
+            kind: IssueKind::Other,
+            message,
+        }),
+    }
+    Ok(())
+}
+
+/// Submit form data to the JSM service desk using the REST API, then attach its files
+pub async fn submit_form(
+    client: &Client,
+    config: &JsmConfig,
+    form_data: FormData,
+) -> crate::error::Result<CreatedRequest> {
+    let attachments = form_data.attachments.clone();
+    let created = create_request(client, config, form_data).await?;
+    attach_to_created(client, config, &created, &attachments).await?;
+    Ok(created)
+}
+
+/// Upload and attach files to a request that was just created
+pub async fn attach_to_created(
+    client: &Client,
+    config: &JsmConfig,
+    created: &CreatedRequest,
+    attachments: &[std::path::PathBuf],
+) -> crate::error::Result<()> {
+    if attachments.is_empty() {
+        return Ok(());
+    }
+    crate::attachment::attach_files(
+        client,
+        config,
+        &created.service_desk_id,
+        &created.issue_key,
+        attachments,
+    )
+    .await
+    .with_context(|| {
+        format!(
+            "Request {} was created, but attaching files failed; retry with `attach -i {}`",
+            created.issue_key, created.issue_key
+        )
+    })?;
+    Ok(())
+}
+
+/// Resolve people, services, dates and option labels in the payload, and check it
+/// against the request type's field list
+async fn resolve_and_validate(
+    client: &Client,
+    config: &JsmConfig,
+    mut payload: CreateRequestPayload,
+    schema: &crate::schema::RequestTypeFields,
+) -> Result<(CreateRequestPayload, Vec<crate::schema::FieldIssue>)> {
+    let mut issues = resolve_people(client, config, &mut payload, schema).await?;
+    issues.extend(
+        crate::services::resolve_service_fields(
+            client,
+            config,
+            &mut payload.request_field_values,
+            schema,
+        )
+        .await?,
+    );
+    issues.extend(resolve_offline(config, &mut payload, schema)?);
+    issues.extend(crate::schema::validate_fields(
+        &payload.request_field_values,
+        schema,
+    ));
+    Ok((payload, issues))
+}
+
+/// Create the request without its attachments; the files are only checked to exist
+pub async fn create_request(
+    client: &Client,
+    config: &JsmConfig,
+    form_data: FormData,
+) -> crate::error::Result<CreatedRequest> {
+    crate::discovery::require_target(config)?;
+    // Use the Atlassian Service Desk REST API to create a customer request
+    let create_request_url = format!("{}/rest/servicedeskapi/request", config.base_url);
+
+    crate::attachment::check_attachments(&form_data.attachments)?;
+    let payload = build_payload(config, form_data)?;
+
+    // Resolve option display names and validate against the request type's field list
+    // before sending anything
+    let snapshot = crate::snapshot::fresh_snapshot_fields(config);
+    let from_snapshot = snapshot.is_some();
+    let mut schema = match snapshot {
+        Some(schema) => schema,
+        None => crate::snapshot::live_request_type_fields(client, config).await?,
     };
+    let (mut request_payload, mut issues) =
+        resolve_and_validate(client, config, payload.clone(), &schema).await?;
+    // The field list may have changed since the snapshot was taken
+    if from_snapshot
+        && issues
+            .iter()
+            .any(crate::schema::FieldIssue::depends_on_field_list)
+    {
+        crate::log_info!("Checking the ticket against the live field list of the request type");
+        schema = crate::snapshot::live_request_type_fields(client, config).await?;
+        (request_payload, issues) = resolve_and_validate(client, config, payload, &schema).await?;
+    }
+    if !issues.is_empty() {
+        return Err(JsmError::Validation {
+            request_type_id: config.request_type_id,
+            issues,
+        });
+    }
+    crate::log_info!(
+        "Validated {} fields against request type {}",
+        request_payload.request_field_values.len(),
+        config.request_type_id
+    );
 
     crate::log_info!("Creating service desk request via API...");
 
-    let response = client
-        .post(&create_request_url)
-        .basic_auth(
-            &config.auth.username,
-            Some(&config.auth.token_atlassian_api),
-        )
-        .header("Accept", "application/json")
-        .header("Content-Type", "application/json")
-        .json(&request_payload)
-        .send()
-        .await
-        .context("Failed to submit service desk request")?;
+    let response = crate::http::send(
+        &config.http,
+        client
+            .post(&create_request_url)
+            .basic_auth(
+                &config.auth.username,
+                Some(&config.auth.token_atlassian_api),
+            )
+            .header("Accept", "application/json")
+            .header("Content-Type", "application/json")
+            .json(&request_payload),
+    )
+    .await?;
 
     if response.status().is_success() {
-        let response_body: CreateRequestResponse =
-            response.json().await.context("Failed to parse response")?;
-
-        crate::log_info!("Service desk request created successfully!");
-        crate::log_info!("Request ID: {}", response_body.issue_key);
-        crate::log_info!(
-            "Request URL: {}/browse/{}",
-            config.base_url,
-            response_body.issue_key
-        );
-        Ok(())
+        let raw: serde_json::Value = response.json().await.context("Failed to parse response")?;
+        let created = CreatedRequest::from_response(config, raw)?;
+        crate::log_debug!("Service desk request created: {}", created.issue_key);
+        Ok(created)
     } else {
         let status = response.status();
         let error_body = response.text().await.unwrap_or_default();
 
         crate::log_error!("Request creation failed with status: {}", status);
-        crate::log_error!("Error details: {}", error_body);
-
-        if status == 400 {
-            Err(anyhow::anyhow!(
-                "Bad request: Check that your portal_id ({}) and request_type_id ({}) are correct, and that all required fields are provided.\nError details: {}",
-                config.portal_id,
-                config.request_type_id,
-                error_body
-            ))
-        } else if status == 401 {
-            Err(anyhow::anyhow!(
-                "Authentication failed. Make sure you're using a valid API token as password."
-            ))
-        } else if status == 403 {
-            Err(anyhow::anyhow!(
-                "Access denied. You may not have permission to create requests in this service desk."
-            ))
-        } else {
-            Err(anyhow::anyhow!(
-                "Request creation failed with status: {} - {}",
-                status,
-                error_body
-            ))
+        crate::log_debug!("Error details: {}", error_body);
+
+        Err(match status.as_u16() {
+            401 => JsmError::Auth {
+                status: 401,
+                url: create_request_url,
+                message: "Make sure you're using a valid API token as password.".to_string(),
+            },
+            403 => JsmError::Forbidden {
+                status: 403,
+                url: create_request_url,
+                message: "You may not have permission to create requests in this service desk."
+                    .to_string(),
+            },
+            status => {
+                // Name the offending fields using the request type's field list
+                let error = JsmError::from_status(status, create_request_url, error_body)
+                    .with_field_names(&schema);
+                if status == 400 && error.field_issues().is_empty() {
+                    crate::log_error!(
+                        "Check that your portal_id ({}) and request_type_id ({}) are correct, and that all required fields are provided.",
+                        config.portal_id,
+                        config.request_type_id
+                    );
+                }
+                error
+            }
+        })
+    }
+}
+
+/// Fetch an existing request by issue key
+pub async fn fetch_request(
+    client: &Client,
+    config: &JsmConfig,
+    issue_key: &str,
+) -> crate::error::Result<CreatedRequest> {
+    let request_url = format!(
+        "{}/rest/servicedeskapi/request/{}",
+        config.base_url, issue_key
+    );
+    let response = crate::http::send(
+        &config.http,
+        client
+            .get(&request_url)
+            .basic_auth(
+                &config.auth.username,
+                Some(&config.auth.token_atlassian_api),
+            )
+            .header("Accept", "application/json"),
+    )
+    .await?;
+
+    if !response.status().is_success() {
+        return Err(JsmError::from_response(response).await);
+    }
+    let raw: serde_json::Value = response
+        .json()
+        .await
+        .with_context(|| format!("Failed to parse request {}", issue_key))?;
+    Ok(CreatedRequest::from_response(config, raw)?)
+}
+
+/// Poll the request until JSM serves it, so follow-up steps do not hit a 404 while the
+/// new issue is still being indexed
+pub async fn wait_until_viewable(
+    client: &Client,
+    config: &JsmConfig,
+    issue_key: &str,
+    timeout: Duration,
+) -> crate::error::Result<()> {
+    let request_url = format!(
+        "{}/rest/servicedeskapi/request/{}",
+        config.base_url, issue_key
+    );
+    let deadline = Instant::now() + timeout;
+    let mut delay = Duration::from_millis(500);
+
+    loop {
+        let response = crate::http::send(
+            &config.http,
+            client
+                .get(&request_url)
+                .basic_auth(
+                    &config.auth.username,
+                    Some(&config.auth.token_atlassian_api),
+                )
+                .header("Accept", "application/json"),
+        )
+        .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            crate::log_debug!("Request {} is viewable", issue_key);
+            return Ok(());
+        }
+        if status != 404 {
+            return Err(JsmError::from_response(response).await);
+        }
+        if Instant::now() + delay > deadline {
+            crate::log_warn!(
+                "Request {} was not viewable after {}s",
+                issue_key,
+                timeout.as_secs()
+            );
+            return Err(JsmError::Timeout { url: request_url });
         }
+
+        crate::log_debug!(
+            "Request {} not viewable yet, retrying in {:?}",
+            issue_key,
+            delay
+        );
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(Duration::from_secs(5));
     }
 }
 
 /// Payload for creating a service desk request via REST API
-#[derive(Debug, Serialize)]
-struct CreateRequestPayload {
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateRequestPayload {
     #[serde(rename = "serviceDeskId")]
-    service_desk_id: u32,
+    pub service_desk_id: u32,
     #[serde(rename = "requestTypeId")]
-    request_type_id: u32,
+    pub request_type_id: u32,
     #[serde(rename = "requestFieldValues")]
-    request_field_values: std::collections::HashMap<String, serde_json::Value>,
+    pub request_field_values: std::collections::BTreeMap<String, serde_json::Value>,
     #[serde(rename = "raiseOnBehalfOf", skip_serializing_if = "Option::is_none")]
-    raise_on_behalf_of: Option<String>,
+    pub raise_on_behalf_of: Option<String>,
+    #[serde(rename = "requestParticipants", skip_serializing_if = "Vec::is_empty")]
+    pub request_participants: Vec<String>,
 }
 
 /// Response from creating a service desk request
@@ -112,4 +423,313 @@ struct CreateRequestResponse {
     request_type_id: String,
     #[serde(rename = "serviceDeskId")]
     service_desk_id: String,
+    #[serde(rename = "_links", default)]
+    links: Option<ResponseLinks>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseLinks {
+    web: Option<String>,
+}
+
+/// A service desk request created by `submit_form`
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedRequest {
+    /// Issue key, e.g. `ITH-66778`
+    pub issue_key: String,
+    pub issue_id: String,
+    pub request_type_id: String,
+    pub service_desk_id: String,
+    /// Agent view of the issue (`/browse/<key>`)
+    pub browse_url: String,
+    /// Customer portal view of the request
+    pub portal_url: String,
+    /// The full response body returned by JSM
+    pub raw: serde_json::Value,
+}
+
+impl CreatedRequest {
+    fn from_response(config: &JsmConfig, raw: serde_json::Value) -> Result<Self> {
+        let response: CreateRequestResponse = serde_json::from_value(raw.clone())
+            .context("Unexpected response from request creation")?;
+        let portal_url = response
+            .links
+            .and_then(|links| links.web)
+            .unwrap_or_else(|| {
+                format!(
+                    "{}/servicedesk/customer/portal/{}/{}",
+                    config.base_url, config.portal_id, response.issue_key
+                )
+            });
+        Ok(Self {
+            browse_url: format!("{}/browse/{}", config.base_url, response.issue_key),
+            portal_url,
+            issue_key: response.issue_key,
+            issue_id: response.issue_id,
+            request_type_id: response.request_type_id,
+            service_desk_id: response.service_desk_id,
+            raw,
+        })
+    }
+}
+
+impl std::fmt::Display for CreatedRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Request ID: {}", self.issue_key)?;
+        writeln!(f, "Request URL: {}", self.browse_url)?;
+        write!(f, "Portal URL: {}", self.portal_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn created_request_from_response() {
+        let config = crate::config::create_default_config();
+        let raw = json!({
+            "issueId": "107001",
+            "issueKey": "ITH-66778",
+            "requestTypeId": "73",
+            "serviceDeskId": "6",
+            "_links": { "web": "https://example.atlassian.net/servicedesk/customer/portal/6/ITH-66778" }
+        });
+        let created = CreatedRequest::from_response(&config, raw.clone()).unwrap();
+        assert_eq!(created.issue_key, "ITH-66778");
+        assert_eq!(
+            created.browse_url,
+            "https://your-organization.atlassian.net/browse/ITH-66778"
+        );
+        assert_eq!(
+            created.portal_url,
+            "https://example.atlassian.net/servicedesk/customer/portal/6/ITH-66778"
+        );
+        assert_eq!(created.raw, raw);
+        assert!(created.to_string().starts_with("Request ID: ITH-66778\n"));
+
+        let without_links = json!({
+            "issueId": "1", "issueKey": "ITH-1", "requestTypeId": "73", "serviceDeskId": "6"
+        });
+        let created = CreatedRequest::from_response(&config, without_links).unwrap();
+        assert_eq!(
+            created.portal_url,
+            "https://your-organization.atlassian.net/servicedesk/customer/portal/6/ITH-1"
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_attachments_keep_the_created_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("runbook.txt");
+        std::fs::write(&path, "steps").unwrap();
+        let mut config = crate::config::create_default_config();
+        // Nothing listens on port 1, so the upload fails to connect
+        config.base_url = "http://127.0.0.1:1".to_string();
+        config.http.max_retries = 0;
+        let raw = json!({
+            "issueId": "1", "issueKey": "ITH-7", "requestTypeId": "73", "serviceDeskId": "6"
+        });
+        let created = CreatedRequest::from_response(&config, raw).unwrap();
+
+        let error = attach_to_created(
+            &Client::new(),
+            &config,
+            &created,
+            std::slice::from_ref(&path),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("retry with `attach -i ITH-7`"),
+            "{:#}",
+            error
+        );
+        assert_eq!(error.exit_code(), 8);
+    }
+
+    #[tokio::test]
+    async fn rechecks_a_stale_snapshot_against_the_live_field_list() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let live = json!({
+            "requestTypeFields": [
+                { "fieldId": "summary", "name": "Summary", "jiraSchema": { "type": "string" } },
+                { "fieldId": "customfield_10900", "name": "Rollback plan",
+                  "jiraSchema": { "type": "string" } }
+            ]
+        });
+        Mock::given(method("GET"))
+            .and(path(
+                "/rest/servicedeskapi/servicedesk/6/requesttype/73/field",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(live))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/rest/servicedeskapi/request"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "issueId": "10001", "issueKey": "ITH-2",
+                "requestTypeId": "73", "serviceDeskId": "6"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        config.http.max_retries = 0;
+        config.schema_cache.dir = dir.path().to_path_buf();
+        // Cached before the admin added "Rollback plan"
+        let cached: crate::schema::RequestTypeFields = serde_json::from_value(json!({
+            "requestTypeFields": [
+                { "fieldId": "summary", "name": "Summary", "jiraSchema": { "type": "string" } }
+            ]
+        }))
+        .unwrap();
+        crate::snapshot::save_snapshot(
+            &config,
+            &crate::snapshot::SchemaSnapshot::new(&config, cached),
+        )
+        .unwrap();
+
+        let form_data = FormData::new(std::collections::HashMap::from([
+            ("summary".to_string(), json!("Deploy")),
+            ("customfield_10900".to_string(), json!("Redeploy 2.3.0")),
+        ]));
+        let created = create_request(&Client::new(), &config, form_data)
+            .await
+            .unwrap();
+        assert_eq!(created.issue_key, "ITH-2");
+        let snapshot = crate::snapshot::load_snapshot(&config).unwrap().unwrap();
+        assert!(snapshot.fields.field("customfield_10900").is_some());
+    }
+
+    #[tokio::test]
+    async fn rechecks_a_stale_option_list_against_the_live_field_list() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let impact = |options: serde_json::Value| {
+            json!({
+                "requestTypeFields": [
+                    { "fieldId": "summary", "name": "Summary", "jiraSchema": { "type": "string" } },
+                    { "fieldId": "customfield_10900", "name": "Impact",
+                      "jiraSchema": { "type": "option" }, "validValues": options }
+                ]
+            })
+        };
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/rest/servicedeskapi/servicedesk/6/requesttype/73/field",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(impact(json!([
+                { "value": "10001", "label": "Low" },
+                { "value": "10004", "label": "Critical" }
+            ]))))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/rest/servicedeskapi/request"))
+            .and(body_partial_json(json!({
+                "requestFieldValues": { "customfield_10900": { "id": "10004" } }
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "issueId": "10001", "issueKey": "ITH-3",
+                "requestTypeId": "73", "serviceDeskId": "6"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        config.http.max_retries = 0;
+        config.schema_cache.dir = dir.path().to_path_buf();
+        // Cached before the admin added the "Critical" option
+        let cached: crate::schema::RequestTypeFields =
+            serde_json::from_value(impact(json!([{ "value": "10001", "label": "Low" }]))).unwrap();
+        crate::snapshot::save_snapshot(
+            &config,
+            &crate::snapshot::SchemaSnapshot::new(&config, cached),
+        )
+        .unwrap();
+
+        let form_data = FormData::new(std::collections::HashMap::from([
+            ("summary".to_string(), json!("Deploy")),
+            ("customfield_10900".to_string(), json!("Critical")),
+        ]));
+        let created = create_request(&Client::new(), &config, form_data)
+            .await
+            .unwrap();
+        assert_eq!(created.issue_key, "ITH-3");
+    }
+
+    #[tokio::test]
+    async fn people_the_request_type_does_not_allow_are_validation_issues() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let schema = json!({
+            "requestTypeFields": [
+                { "fieldId": "summary", "name": "Summary", "jiraSchema": { "type": "string" } }
+            ],
+            "canRaiseOnBehalfOf": false,
+            "canAddRequestParticipants": false
+        });
+        let server = MockServer::start().await;
+        // The cached field list is rechecked before the ticket is rejected
+        Mock::given(method("GET"))
+            .and(path(
+                "/rest/servicedeskapi/servicedesk/6/requesttype/73/field",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(schema.clone()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        config.http.max_retries = 0;
+        config.schema_cache.dir = dir.path().to_path_buf();
+        crate::snapshot::save_snapshot(
+            &config,
+            &crate::snapshot::SchemaSnapshot::new(&config, serde_json::from_value(schema).unwrap()),
+        )
+        .unwrap();
+
+        let form_data = FormData {
+            raise_on_behalf_of: Some("ann@example.com".to_string()),
+            request_participants: vec!["bob@example.com".to_string()],
+            ..FormData::new(std::collections::HashMap::from([(
+                "summary".to_string(),
+                json!("Deploy"),
+            )]))
+        };
+        let error = create_request(&Client::new(), &config, form_data)
+            .await
+            .unwrap_err();
+        assert_eq!(error.exit_code(), 6);
+        let fields: Vec<(&str, IssueKind)> = error
+            .field_issues()
+            .iter()
+            .map(|issue| (issue.field_id.as_str(), issue.kind))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("raise_on_behalf_of", IssueKind::NotAllowed),
+                ("request_participants", IssueKind::NotAllowed)
+            ]
+        );
+    }
 }
@@ -0,0 +1,282 @@
+use crate::error::{JsmError, Result};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// HTTP settings from the `[http]` section of the config file
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HttpConfig {
+    /// Retries after the first attempt; 0 disables retrying
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled on every further retry
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Upper bound for a single delay, including one requested by `Retry-After`
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Timeout of a single attempt
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+fn default_timeout_secs() -> u64 {
+    60
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+}
+
+impl HttpConfig {
+    /// True when no setting differs from the defaults
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Exponential backoff for the given retry (0-based), with "equal jitter": a random
+    /// delay between half and all of the exponential step
+    fn backoff(&self, retry: u32) -> Duration {
+        let step = self
+            .initial_backoff_ms
+            .saturating_mul(1u64 << retry.min(20))
+            .min(self.max_backoff_ms);
+        let half = step / 2;
+        Duration::from_millis(half + random_u64() % (step - half + 1))
+    }
+
+    /// Delay before the next attempt, preferring the server's `Retry-After`
+    fn delay(&self, retry: u32, response: Option<&Response>) -> Duration {
+        let max = Duration::from_millis(self.max_backoff_ms);
+        response
+            .and_then(retry_after)
+            .unwrap_or_else(|| self.backoff(retry))
+            .min(max)
+    }
+}
+
+/// Random number from the standard library's per-process hash keys
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default(),
+    );
+    hasher.finish()
+}
+
+/// Parse `Retry-After` as delay-seconds or an HTTP date
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+        .to_std()
+        .ok()
+}
+
+/// Whether a request with this method may be sent twice without side effects
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    )
+}
+
+/// Statuses worth retrying. A POST is only retried when the server certainly did not
+/// process it: 429 (rate limited) and 503 (not accepting requests). A 502 or 504 may
+/// come from a proxy after the request was already handled.
+fn is_retryable_status(status: StatusCode, idempotent: bool) -> bool {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+        StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => idempotent,
+        _ => false,
+    }
+}
+
+/// Transport errors worth retrying. A POST is only retried when the connection was
+/// never established; after a timeout or reset the request may have been created.
+fn is_retryable_error(error: &reqwest::Error, idempotent: bool) -> bool {
+    error.is_connect() || (idempotent && (error.is_timeout() || error.is_request()))
+}
+
+/// Send a request, retrying transient failures according to `http`.
+///
+/// Requests whose body cannot be cloned (multipart uploads) are sent once. The last
+/// response is returned as-is when retries run out, so callers handle statuses as before.
+pub async fn send(http: &HttpConfig, request: RequestBuilder) -> Result<Response> {
+    let (client, request) = request.build_split();
+    let request = request.map_err(|source| JsmError::network("<invalid request>", source))?;
+    let url = request.url().to_string();
+    let idempotent = is_idempotent(request.method());
+
+    let mut retry = 0;
+    loop {
+        let attempt = match request.try_clone() {
+            Some(attempt) if retry < http.max_retries => attempt,
+            // Last attempt, or a body that can only be sent once
+            _ => {
+                return client
+                    .execute(request)
+                    .await
+                    .map_err(|source| JsmError::network(&url, source));
+            }
+        };
+
+        let delay = match client.execute(attempt).await {
+            Ok(response) if is_retryable_status(response.status(), idempotent) => {
+                let delay = http.delay(retry, Some(&response));
+                crate::log_warn!(
+                    "{} {} returned {}; retrying in {:?} ({}/{})",
+                    request.method(),
+                    url,
+                    response.status(),
+                    delay,
+                    retry + 1,
+                    http.max_retries
+                );
+                delay
+            }
+            Ok(response) => return Ok(response),
+            Err(error) if is_retryable_error(&error, idempotent) => {
+                let delay = http.delay(retry, None);
+                crate::log_warn!(
+                    "{} {} failed: {}; retrying in {:?} ({}/{})",
+                    request.method(),
+                    url,
+                    error,
+                    delay,
+                    retry + 1,
+                    http.max_retries
+                );
+                delay
+            }
+            Err(error) => return Err(JsmError::network(&url, error)),
+        };
+
+        tokio::time::sleep(delay).await;
+        retry += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn fast() -> HttpConfig {
+        HttpConfig {
+            max_retries: 2,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 10,
+            timeout_secs: 5,
+        }
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        let http = HttpConfig {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1_000,
+            ..HttpConfig::default()
+        };
+        for _ in 0..20 {
+            let first = http.backoff(0).as_millis();
+            assert!((50..=100).contains(&first), "{}", first);
+            let third = http.backoff(2).as_millis();
+            assert!((200..=400).contains(&third), "{}", third);
+            assert!(http.backoff(10).as_millis() <= 1_000);
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_get_after_transient_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rest/servicedeskapi/servicedesk"))
+            .respond_with(ResponseTemplate::new(502))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/rest/servicedeskapi/servicedesk"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/rest/servicedeskapi/servicedesk"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let url = format!("{}/rest/servicedeskapi/servicedesk", server.uri());
+        let response = send(&fast(), client.get(&url)).await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn post_is_not_retried_after_a_possibly_processed_failure() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/rest/servicedeskapi/request"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let url = format!("{}/rest/servicedeskapi/request", server.uri());
+        let response = send(&fast(), client.post(&url).body("{}")).await.unwrap();
+        assert_eq!(response.status(), 502);
+    }
+
+    #[tokio::test]
+    async fn post_is_retried_when_rate_limited_until_retries_run_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/rest/servicedeskapi/request"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let url = format!("{}/rest/servicedeskapi/request", server.uri());
+        let response = send(&fast(), client.post(&url).body("{}")).await.unwrap();
+        assert_eq!(response.status(), 429);
+    }
+}
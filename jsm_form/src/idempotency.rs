@@ -0,0 +1,591 @@
+use crate::error::JsmError;
+use crate::form::CreatedRequest;
+use crate::{FormData, JsmConfig};
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Text appended to the request description so a submission can be found again with JQL
+pub const MARKER_PREFIX: &str = "jsm_form idempotency key: ";
+
+/// State file used when there is no ticket file to put it next to
+pub const DEFAULT_STATE_FILE: &str = "jsm-state.json";
+
+/// Idempotency settings of a single submission
+#[derive(Debug, Clone)]
+pub struct Idempotency {
+    pub key: String,
+    /// Local record of the submission, normally `<ticket>.jsm-state.json`
+    pub state_file: PathBuf,
+    /// Also search Jira for a request carrying the key before creating one
+    pub check_remote: bool,
+}
+
+/// Where an existing request was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExistingSource {
+    LocalState,
+    RemoteSearch,
+}
+
+/// Outcome of [`submit_once`]
+#[derive(Debug, Clone)]
+pub enum Submission {
+    Created(CreatedRequest),
+    Existing {
+        request: CreatedRequest,
+        source: ExistingSource,
+    },
+}
+
+impl Submission {
+    pub fn request(&self) -> &CreatedRequest {
+        match self {
+            Submission::Created(request) | Submission::Existing { request, .. } => request,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionStatus {
+    /// The request is about to be, or may have been, created
+    Pending,
+    Created,
+}
+
+/// Contents of the state file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmissionState {
+    pub idempotency_key: String,
+    pub status: SubmissionStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue_key: Option<String>,
+    pub updated_at: String,
+    /// When `change` completed the risk assessment of the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk_assessment_completed_at: Option<String>,
+}
+
+/// Derive a key from the merged ticket data (file, `-j` and `-d` sources, attachments and
+/// people) and the request type it is submitted to
+pub fn derive_key(config: &JsmConfig, form_data: &FormData) -> Result<String> {
+    // Sorted keys, so the same data always hashes the same
+    let fields: BTreeMap<&String, &Value> = form_data.fields.iter().collect();
+    let data = serde_json::to_vec(&serde_json::json!({
+        "fields": fields,
+        "attachments": form_data.attachments,
+        "raise_on_behalf_of": form_data.raise_on_behalf_of,
+        "request_participants": form_data.request_participants,
+    }))?;
+    let mut hasher = Sha256::new();
+    hasher.update(config.base_url.as_bytes());
+    hasher.update(config.portal_id.to_be_bytes());
+    hasher.update(config.request_type_id.to_be_bytes());
+    hasher.update(&data);
+    Ok(hasher.finalize()[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Check an explicit key. Keys end up in JQL, so only a safe character set is allowed.
+pub fn validate_key(key: &str) -> Result<()> {
+    let valid = (8..=64).contains(&key.len())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Idempotency key '{}' must be 8-64 characters of letters, digits, '-' or '_'",
+            key
+        ))
+    }
+}
+
+/// State file next to the ticket file: `changes/ticket.toml` -> `changes/ticket.jsm-state.json`
+pub fn state_path(ticket: Option<&Path>) -> PathBuf {
+    match ticket {
+        Some(ticket) => ticket.with_extension("jsm-state.json"),
+        None => PathBuf::from(DEFAULT_STATE_FILE),
+    }
+}
+
+/// Append the key marker to a description
+pub fn mark_description(description: &str, key: &str) -> String {
+    format!("{}\n\n{}{}", description.trim_end(), MARKER_PREFIX, key)
+}
+
+pub fn load_state(path: &Path) -> Result<Option<SubmissionState>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read state file: {}", path.display()))?;
+    let state = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse state file: {}", path.display()))?;
+    Ok(Some(state))
+}
+
+pub fn save_state(
+    path: &Path,
+    key: &str,
+    status: SubmissionStatus,
+    issue_key: Option<&str>,
+) -> Result<()> {
+    let state = SubmissionState {
+        idempotency_key: key.to_string(),
+        status,
+        issue_key: issue_key.map(str::to_string),
+        updated_at: chrono::Utc::now().to_rfc3339(),
+        risk_assessment_completed_at: None,
+    };
+    write_state(path, &state)
+}
+
+fn write_state(path: &Path, state: &SubmissionState) -> Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(state)?)
+        .with_context(|| format!("Failed to write state file: {}", path.display()))
+}
+
+/// Whether the state file records a completed risk assessment for this key
+pub fn risk_assessment_completed(idempotency: &Idempotency) -> Result<bool> {
+    Ok(load_state(&idempotency.state_file)?.is_some_and(|state| {
+        state.idempotency_key == idempotency.key && state.risk_assessment_completed_at.is_some()
+    }))
+}
+
+/// Record in the state file that the risk assessment of the created request is done
+pub fn record_risk_assessment(idempotency: &Idempotency) -> Result<()> {
+    let mut state = load_state(&idempotency.state_file)?
+        .filter(|state| state.idempotency_key == idempotency.key)
+        .with_context(|| {
+            format!(
+                "No submission with key {} in {}",
+                idempotency.key,
+                idempotency.state_file.display()
+            )
+        })?;
+    let now = chrono::Utc::now().to_rfc3339();
+    state.risk_assessment_completed_at = Some(now.clone());
+    state.updated_at = now;
+    write_state(&idempotency.state_file, &state)
+}
+
+/// Search Jira for a request whose description carries the key marker
+pub async fn search_remote(
+    client: &Client,
+    config: &JsmConfig,
+    key: &str,
+) -> Result<Option<String>> {
+    #[derive(Deserialize)]
+    struct SearchResults {
+        #[serde(default)]
+        issues: Vec<Issue>,
+    }
+    #[derive(Deserialize)]
+    struct Issue {
+        key: String,
+    }
+
+    let search_url = format!("{}/rest/api/3/search/jql", config.base_url);
+    let jql = format!("description ~ \"\\\"{}\\\"\" ORDER BY created ASC", key);
+    let response = crate::http::send(
+        &config.http,
+        client
+            .get(&search_url)
+            .query(&[
+                ("jql", jql.as_str()),
+                ("fields", "key"),
+                ("maxResults", "1"),
+            ])
+            .basic_auth(
+                &config.auth.username,
+                Some(&config.auth.token_atlassian_api),
+            )
+            .header("Accept", "application/json"),
+    )
+    .await
+    .context("Failed to search for an existing request")?;
+
+    if !response.status().is_success() {
+        return Err(JsmError::from_response(response).await.into());
+    }
+    let results: SearchResults = response
+        .json()
+        .await
+        .context("Failed to parse search results")?;
+    Ok(results.issues.into_iter().next().map(|issue| issue.key))
+}
+
+/// Create the request unless this key was already submitted.
+///
+/// The local state file is checked first. A `pending` entry means an earlier run may
+/// have created the request without seeing the response, so Jira is searched for the
+/// marker even without `check_remote`.
+pub async fn submit_once(
+    client: &Client,
+    config: &JsmConfig,
+    mut form_data: FormData,
+    idempotency: &Idempotency,
+) -> crate::error::Result<Submission> {
+    let key = &idempotency.key;
+    let state = load_state(&idempotency.state_file)?.filter(|state| &state.idempotency_key == key);
+
+    if let Some(SubmissionState {
+        status: SubmissionStatus::Created,
+        issue_key: Some(issue_key),
+        ..
+    }) = &state
+    {
+        crate::log_info!(
+            "Ticket already submitted as {} (see {})",
+            issue_key,
+            idempotency.state_file.display()
+        );
+        let request = crate::form::fetch_request(client, config, issue_key).await?;
+        return Ok(Submission::Existing {
+            request,
+            source: ExistingSource::LocalState,
+        });
+    }
+
+    // The marker goes into the description, which may be set through an alias
+    let has_description = matches!(
+        crate::aliases::resolve_aliases(form_data.fields.clone(), &config.aliases)?
+            .get("description"),
+        Some(Value::String(_))
+    );
+    let was_pending = state.is_some();
+    if !has_description && (idempotency.check_remote || was_pending) {
+        let remedy = if was_pending {
+            format!(
+                "add a description, or delete {} if the earlier run created no request",
+                idempotency.state_file.display()
+            )
+        } else {
+            "add a description or leave out --check-remote".to_string()
+        };
+        return Err(JsmError::Config {
+            path: None,
+            message: format!(
+                "the ticket has no description to carry the idempotency marker, so Jira cannot be searched for key {}; {}",
+                key, remedy
+            ),
+        });
+    }
+    if idempotency.check_remote || was_pending {
+        if was_pending {
+            crate::log_warn!(
+                "A previous submission with key {} did not finish; checking Jira for it",
+                key
+            );
+        }
+        if let Some(issue_key) = search_remote(client, config, key).await? {
+            crate::log_info!("Found existing request {} with key {}", issue_key, key);
+            save_state(
+                &idempotency.state_file,
+                key,
+                SubmissionStatus::Created,
+                Some(&issue_key),
+            )?;
+            let request = crate::form::fetch_request(client, config, &issue_key).await?;
+            return Ok(Submission::Existing {
+                request,
+                source: ExistingSource::RemoteSearch,
+            });
+        }
+    }
+
+    if !has_description {
+        crate::log_warn!(
+            "The ticket has no description to carry the idempotency marker; duplicates can only be detected through {}",
+            idempotency.state_file.display()
+        );
+    }
+    form_data.idempotency_key = Some(key.clone());
+
+    save_state(
+        &idempotency.state_file,
+        key,
+        SubmissionStatus::Pending,
+        None,
+    )?;
+    let attachments = form_data.attachments.clone();
+    match crate::form::create_request(client, config, form_data).await {
+        Ok(created) => {
+            // Recorded before attaching, so a failed upload is not submitted again
+            save_state(
+                &idempotency.state_file,
+                key,
+                SubmissionStatus::Created,
+                Some(&created.issue_key),
+            )?;
+            crate::form::attach_to_created(client, config, &created, &attachments).await?;
+            Ok(Submission::Created(created))
+        }
+        Err(error) => {
+            // Forget the pending entry only when the request was certainly not created. A
+            // 5xx may come after JSM created it, so the next run must search for the marker.
+            let rejected = match &error {
+                JsmError::Validation { .. }
+                | JsmError::Auth { .. }
+                | JsmError::Forbidden { .. } => true,
+                JsmError::Api { status, .. } => (400..500).contains(status),
+                _ => false,
+            };
+            if rejected {
+                std::fs::remove_file(&idempotency.state_file).ok();
+            }
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_keys_from_the_merged_ticket_data() {
+        let form_data = |summary: &str| {
+            FormData::new(std::collections::HashMap::from([
+                ("summary".to_string(), Value::from(summary)),
+                ("description".to_string(), Value::from("Deploy 2.3.1")),
+            ]))
+        };
+
+        let mut config = crate::config::create_default_config();
+        let key = derive_key(&config, &form_data("A")).unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(derive_key(&config, &form_data("A")).unwrap(), key);
+        validate_key(&key).unwrap();
+
+        // e.g. the same ticket file with `-d summary=B`
+        assert_ne!(derive_key(&config, &form_data("B")).unwrap(), key);
+        let mut with_participant = form_data("A");
+        with_participant
+            .request_participants
+            .push("ann@example.com".to_string());
+        assert_ne!(derive_key(&config, &with_participant).unwrap(), key);
+
+        config.request_type_id += 1;
+        assert_ne!(derive_key(&config, &form_data("A")).unwrap(), key);
+    }
+
+    #[tokio::test]
+    async fn records_the_request_before_attaching_files() {
+        use serde_json::json;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/rest/servicedeskapi/servicedesk/6/requesttype/73/field",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "requestTypeFields": [{ "fieldId": "summary", "name": "Summary",
+                                        "jiraSchema": { "type": "string" } }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/rest/servicedeskapi/request"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "issueId": "10001", "issueKey": "ITH-1",
+                "requestTypeId": "73", "serviceDeskId": "6"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(
+                "/rest/servicedeskapi/servicedesk/6/attachTemporaryFile",
+            ))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let attachment = dir.path().join("runbook.txt");
+        std::fs::write(&attachment, "steps").unwrap();
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        config.http.max_retries = 0;
+        config.schema_cache.ttl_secs = 0;
+        let idempotency = Idempotency {
+            key: "abcdef12".to_string(),
+            state_file: dir.path().join("ticket.jsm-state.json"),
+            check_remote: false,
+        };
+        let form_data = FormData {
+            attachments: vec![attachment],
+            ..FormData::new(std::collections::HashMap::from([(
+                "summary".to_string(),
+                json!("Deploy"),
+            )]))
+        };
+
+        let error = submit_once(&Client::new(), &config, form_data, &idempotency)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("attach -i ITH-1"), "{}", error);
+        let state = load_state(&idempotency.state_file).unwrap().unwrap();
+        assert_eq!(state.status, SubmissionStatus::Created);
+        assert_eq!(state.issue_key.as_deref(), Some("ITH-1"));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_pending_state_after_a_server_error() {
+        use serde_json::json;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/rest/servicedeskapi/servicedesk/6/requesttype/73/field",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "requestTypeFields": [{ "fieldId": "summary", "name": "Summary",
+                                        "jiraSchema": { "type": "string" } }]
+            })))
+            .mount(&server)
+            .await;
+        // A proxy error: JSM may have created the request before it was sent
+        Mock::given(method("POST"))
+            .and(path("/rest/servicedeskapi/request"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        config.http.max_retries = 0;
+        config.schema_cache.ttl_secs = 0;
+        let idempotency = Idempotency {
+            key: "abcdef12".to_string(),
+            state_file: dir.path().join("ticket.jsm-state.json"),
+            check_remote: false,
+        };
+        let form_data = FormData::new(std::collections::HashMap::from([(
+            "summary".to_string(),
+            json!("Deploy"),
+        )]));
+
+        let error = submit_once(&Client::new(), &config, form_data, &idempotency)
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), Some(502));
+        let state = load_state(&idempotency.state_file).unwrap().unwrap();
+        assert_eq!(state.status, SubmissionStatus::Pending);
+        assert_eq!(state.idempotency_key, "abcdef12");
+    }
+
+    #[tokio::test]
+    async fn remote_checks_need_a_description_for_the_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::config::create_default_config();
+        // Nothing listens on port 1: the checks must fail before any request is sent
+        config.base_url = "http://127.0.0.1:1".to_string();
+        config.http.max_retries = 0;
+        config
+            .aliases
+            .insert("details".to_string(), "description".to_string());
+        let mut idempotency = Idempotency {
+            key: "abcdef12".to_string(),
+            state_file: dir.path().join("ticket.jsm-state.json"),
+            check_remote: true,
+        };
+        let form_data = || {
+            FormData::new(std::collections::HashMap::from([(
+                "summary".to_string(),
+                Value::from("Deploy"),
+            )]))
+        };
+
+        let error = submit_once(&Client::new(), &config, form_data(), &idempotency)
+            .await
+            .unwrap_err();
+        assert_eq!(error.exit_code(), 3, "{}", error);
+        assert!(error.to_string().contains("--check-remote"), "{}", error);
+
+        idempotency.check_remote = false;
+        save_state(
+            &idempotency.state_file,
+            "abcdef12",
+            SubmissionStatus::Pending,
+            None,
+        )
+        .unwrap();
+        let error = submit_once(&Client::new(), &config, form_data(), &idempotency)
+            .await
+            .unwrap_err();
+        assert_eq!(error.exit_code(), 3, "{}", error);
+        assert!(error.to_string().contains("delete"), "{}", error);
+
+        // A description set through an alias carries the marker, so the search runs
+        let mut with_alias = form_data();
+        with_alias
+            .fields
+            .insert("details".to_string(), Value::from("Deploy 2.3.1"));
+        let error = submit_once(&Client::new(), &config, with_alias, &idempotency)
+            .await
+            .unwrap_err();
+        assert_eq!(error.exit_code(), 8, "{}", error);
+    }
+
+    #[test]
+    fn records_the_risk_assessment_of_a_created_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let idempotency = Idempotency {
+            key: "abcdef12".to_string(),
+            state_file: dir.path().join("ticket.jsm-state.json"),
+            check_remote: false,
+        };
+        save_state(
+            &idempotency.state_file,
+            "abcdef12",
+            SubmissionStatus::Created,
+            Some("ITH-1"),
+        )
+        .unwrap();
+        assert!(!risk_assessment_completed(&idempotency).unwrap());
+
+        record_risk_assessment(&idempotency).unwrap();
+        assert!(risk_assessment_completed(&idempotency).unwrap());
+        let state = load_state(&idempotency.state_file).unwrap().unwrap();
+        assert_eq!(state.issue_key.as_deref(), Some("ITH-1"));
+
+        // A different ticket sharing the state file does not count
+        let other = Idempotency {
+            key: "12345678".to_string(),
+            ..idempotency
+        };
+        assert!(!risk_assessment_completed(&other).unwrap());
+    }
+
+    #[test]
+    fn state_file_sits_next_to_the_ticket() {
+        assert_eq!(
+            state_path(Some(Path::new("changes/ticket.toml"))),
+            PathBuf::from("changes/ticket.jsm-state.json")
+        );
+        assert_eq!(state_path(None), PathBuf::from(DEFAULT_STATE_FILE));
+        assert!(validate_key("CHG-2025-09-23").is_ok());
+        assert!(validate_key("bad key\"").is_err());
+        assert_eq!(
+            mark_description("Upgrade\n", "abc12345"),
+            "Upgrade\n\njsm_form idempotency key: abc12345"
+        );
+    }
+}
@@ -2,16 +2,34 @@
 //!
 //! This library provides functionality to automate completion of JSM (Jira Service Management) web forms.
 
+pub mod aliases;
+pub mod attachment;
 pub mod auth;
 pub mod config;
+pub mod datetime;
+pub mod discovery;
 pub mod error;
 pub mod form;
+pub mod http;
+pub mod idempotency;
 pub mod logging;
+pub mod options;
+pub mod output;
+pub mod schema;
+pub mod services;
+pub mod snapshot;
+pub mod table;
+pub mod template;
+pub mod ticket;
+pub mod users;
+pub mod validate;
 pub mod web;
 
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+pub use error::JsmError;
+pub use form::CreatedRequest;
+
 // Re-export web automation types
 pub use web::{ChangeImpactAssessmentConfig, ChangeRiskAssessmentConfig, RiskAssessmentConfig};
 
@@ -22,12 +40,46 @@ pub struct JsmConfig {
     pub org: String,
     /// Base URL of the JSM instance
     pub base_url: String,
-    /// Portal ID
+    /// Portal ID. Can be left out when `service_desk` is set.
+    #[serde(default)]
     pub portal_id: u32,
-    /// Request type ID
+    /// Request type ID. Can be left out when `request_type` is set.
+    #[serde(default)]
     pub request_type_id: u32,
+    /// Service desk by project key or name (e.g. `ITH`), resolved to `portal_id` at startup
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_desk: Option<String>,
+    /// Request type by name (e.g. `Normal Change`), resolved to `request_type_id` at startup
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_type: Option<String>,
     /// Authentication credentials
     pub auth: AuthConfig,
+    /// Human-friendly names for field IDs, e.g. `planned_start = "customfield_10878"`
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub aliases: std::collections::BTreeMap<String, String>,
+    /// IANA timezone (e.g. `Pacific/Auckland`) for ticket dates without an offset.
+    /// Defaults to the system timezone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Local cache of the service registry used to resolve Affected services by name.
+    /// Defaults to `jsm_services_cache.pvt.json` in the working directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub services_cache: Option<std::path::PathBuf>,
+    /// Timeouts and retries of REST calls (`[http]` section)
+    #[serde(default, skip_serializing_if = "http::HttpConfig::is_default")]
+    pub http: http::HttpConfig,
+    /// On-disk cache of request type field lists (`[schema_cache]` section)
+    #[serde(
+        default,
+        skip_serializing_if = "snapshot::SchemaCacheConfig::is_default"
+    )]
+    pub schema_cache: snapshot::SchemaCacheConfig,
+    /// Profile to apply, usually set with `--profile` or `JSM_PROFILE`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Named overrides of site, target and credentials (`[profiles.<name>]` sections)
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub profiles: std::collections::BTreeMap<String, config::ProfileConfig>,
 }
 
 /// Authentication configuration
@@ -40,13 +92,42 @@ pub struct AuthConfig {
     /// Password used for Microsoft login flow
     #[serde(default)]
     pub microsoft_password: String,
+    /// Shell command printing the API token on its first line of output, e.g.
+    /// `pass show jsm/token`; used when `token_atlassian_api` is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_helper: Option<String>,
+    /// Shell command printing the Microsoft password; used when `microsoft_password` is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub microsoft_password_helper: Option<String>,
 }
 
 /// Form data to be submitted
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct FormData {
     /// Map of field names to values (supports strings, arrays, objects, etc.)
     pub fields: std::collections::HashMap<String, serde_json::Value>,
+    /// Files to attach to the request once it is created
+    #[serde(default)]
+    pub attachments: Vec<std::path::PathBuf>,
+    /// Email address or account ID of the customer the request is raised for
+    #[serde(default)]
+    pub raise_on_behalf_of: Option<String>,
+    /// Email addresses or account IDs to add as request participants
+    #[serde(default)]
+    pub request_participants: Vec<String>,
+    /// Key appended to the description so the request can be found again
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+}
+
+impl FormData {
+    /// Form data with only fields: no attachments, people or idempotency key
+    pub fn new(fields: std::collections::HashMap<String, serde_json::Value>) -> Self {
+        Self {
+            fields,
+            ..Default::default()
+        }
+    }
 }
 
 /// Main JSM form client
@@ -60,6 +141,7 @@ impl JsmFormClient {
     pub fn new(config: JsmConfig) -> Self {
         let client = reqwest::Client::builder()
             .cookie_store(true)
+            .timeout(std::time::Duration::from_secs(config.http.timeout_secs))
             .build()
             .expect("Failed to create HTTP client");
 
@@ -67,12 +149,276 @@ impl JsmFormClient {
     }
 
     /// Authenticate with the JSM instance
-    pub async fn authenticate(&self) -> Result<()> {
-        auth::authenticate(&self.client, &self.config.auth, &self.config.base_url).await
+    pub async fn authenticate(&self) -> error::Result<()> {
+        auth::authenticate(
+            &self.client,
+            &self.config.auth,
+            &self.config.base_url,
+            &self.config.http,
+        )
+        .await
+    }
+
+    /// The configuration, with names resolved once `resolve_target` has run
+    pub fn config(&self) -> &JsmConfig {
+        &self.config
+    }
+
+    /// Resolve `service_desk` and `request_type` names to IDs
+    pub async fn resolve_target(&mut self) -> error::Result<()> {
+        Ok(discovery::resolve_target(&self.client, &mut self.config).await?)
+    }
+
+    /// List the service desks visible to the user
+    pub async fn service_desks(&self) -> error::Result<Vec<discovery::ServiceDesk>> {
+        Ok(discovery::list_service_desks(&self.client, &self.config).await?)
+    }
+
+    /// List the request types of a service desk
+    pub async fn request_types(
+        &self,
+        service_desk_id: &str,
+    ) -> error::Result<Vec<schema::RequestType>> {
+        Ok(discovery::list_request_types(&self.client, &self.config, service_desk_id).await?)
+    }
+
+    /// Fetch the details of the configured request type
+    pub async fn request_type(&self) -> error::Result<schema::RequestType> {
+        Ok(schema::fetch_request_type(&self.client, &self.config).await?)
+    }
+
+    /// Fetch the field list of the configured request type, using the schema cache
+    pub async fn request_type_fields(&self) -> error::Result<schema::RequestTypeFields> {
+        Ok(snapshot::cached_request_type_fields(&self.client, &self.config).await?)
+    }
+
+    /// Fetch the field list of the configured request type, bypassing the schema cache
+    pub async fn live_request_type_fields(&self) -> error::Result<schema::RequestTypeFields> {
+        Ok(schema::fetch_request_type_fields(&self.client, &self.config).await?)
+    }
+
+    /// Fetch the request type and its fields in one go
+    pub async fn analyze(&self) -> error::Result<schema::FormAnalysis> {
+        let request_type = self.request_type().await?;
+        let fields = self.request_type_fields().await?;
+        Ok(schema::FormAnalysis {
+            request_type,
+            fields,
+        })
     }
 
     /// Submit form data to the JSM form
-    pub async fn submit_form(&self, form_data: FormData) -> Result<()> {
+    pub async fn submit_form(&self, form_data: FormData) -> error::Result<CreatedRequest> {
         form::submit_form(&self.client, &self.config, form_data).await
     }
+
+    /// Wait until a newly created request can be fetched
+    pub async fn wait_until_viewable(
+        &self,
+        issue_key: &str,
+        timeout: std::time::Duration,
+    ) -> error::Result<()> {
+        form::wait_until_viewable(&self.client, &self.config, issue_key, timeout).await
+    }
+
+    /// Submit form data unless the same submission already created a request
+    pub async fn submit_form_once(
+        &self,
+        form_data: FormData,
+        idempotency: &idempotency::Idempotency,
+    ) -> error::Result<idempotency::Submission> {
+        idempotency::submit_once(&self.client, &self.config, form_data, idempotency).await
+    }
+
+    /// Upload files and attach them to an existing request
+    pub async fn attach_files(
+        &self,
+        issue_key: &str,
+        paths: &[std::path::PathBuf],
+    ) -> error::Result<()> {
+        // The files are uploaded to the request's service desk, whatever the config targets
+        let request = form::fetch_request(&self.client, &self.config, issue_key).await?;
+        Ok(attachment::attach_files(
+            &self.client,
+            &self.config,
+            &request.service_desk_id,
+            issue_key,
+            paths,
+        )
+        .await?)
+    }
+
+    /// Build the payload without touching the network. With a cached schema, dates and
+    /// option labels are resolved like `submit_form` does; services and people are not.
+    ///
+    /// Keys that are not fields of the cached schema, or without one neither an alias nor
+    /// a field ID, fail with [`JsmError::Validation`]; other problems are only logged.
+    pub fn dry_run_submit_form(
+        &self,
+        form_data: FormData,
+    ) -> error::Result<form::CreateRequestPayload> {
+        let mut payload = form::build_payload(&self.config, form_data)?;
+        let unknown = match snapshot::load_snapshot(&self.config)? {
+            Some(snapshot) => {
+                for issue in form::resolve_offline(&self.config, &mut payload, &snapshot.fields)? {
+                    log_warn!("{}", issue);
+                }
+                schema::validate_fields(&payload.request_field_values, &snapshot.fields)
+                    .into_iter()
+                    .filter(|issue| issue.kind == schema::IssueKind::UnknownField)
+                    .collect()
+            }
+            None => {
+                log_warn!(
+                    "No cached schema for request type {}; dates and option labels are shown as written",
+                    self.config.request_type_id
+                );
+                aliases::check_field_ids(&payload.request_field_values)
+            }
+        };
+        if !unknown.is_empty() {
+            return Err(JsmError::Validation {
+                request_type_id: self.config.request_type_id,
+                issues: unknown,
+            });
+        }
+        Ok(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn client(cache_dir: &std::path::Path) -> JsmFormClient {
+        let mut config = config::create_default_config();
+        config.timezone = Some("UTC".to_string());
+        config.schema_cache.dir = cache_dir.to_path_buf();
+        config
+            .aliases
+            .insert("planned_start".to_string(), "customfield_10878".to_string());
+        JsmFormClient::new(config)
+    }
+
+    fn form_data() -> FormData {
+        FormData {
+            idempotency_key: Some("abcdef12".to_string()),
+            ..FormData::new(std::collections::HashMap::from([
+                ("summary".to_string(), json!("Deploy 2.3.1")),
+                ("description".to_string(), json!("Upgrade the app")),
+                ("planned_start".to_string(), json!("2025-09-23")),
+                ("customfield_10900".to_string(), json!("medium")),
+            ]))
+        }
+    }
+
+    #[test]
+    fn dry_run_resolves_fields_from_the_cached_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = client(dir.path());
+        let fields: schema::RequestTypeFields = serde_json::from_value(json!({
+            "requestTypeFields": [
+                { "fieldId": "summary", "name": "Summary", "jiraSchema": { "type": "string" } },
+                { "fieldId": "description", "name": "Description",
+                  "jiraSchema": { "type": "string" } },
+                { "fieldId": "customfield_10878", "name": "Planned start",
+                  "jiraSchema": { "type": "datetime" } },
+                { "fieldId": "customfield_10900", "name": "Impact",
+                  "jiraSchema": { "type": "option" },
+                  "validValues": [{ "value": "10001", "label": "Low" },
+                                  { "value": "10002", "label": "Medium" }] }
+            ]
+        }))
+        .unwrap();
+        snapshot::save_snapshot(
+            client.config(),
+            &snapshot::SchemaSnapshot::new(client.config(), fields),
+        )
+        .unwrap();
+
+        let payload = client.dry_run_submit_form(form_data()).unwrap();
+        let values = &payload.request_field_values;
+        assert_eq!(values["customfield_10878"], "2025-09-23T00:00:00.000+0000");
+        assert_eq!(values["customfield_10900"], json!({ "id": "10002" }));
+        assert_eq!(
+            values["description"],
+            "Upgrade the app\n\njsm_form idempotency key: abcdef12"
+        );
+        assert!(!values.contains_key("planned_start"));
+
+        let mut typo = form_data();
+        typo.fields
+            .insert("customfield_10999".to_string(), json!("x"));
+        let error = client.dry_run_submit_form(typo).unwrap_err();
+        assert_eq!(error.field_issues()[0].field_id, "customfield_10999");
+    }
+
+    #[tokio::test]
+    async fn attaches_through_the_service_desk_of_the_request() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rest/servicedeskapi/request/OPS-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issueId": "20001", "issueKey": "OPS-1",
+                "requestTypeId": "90", "serviceDeskId": "9"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(
+                "/rest/servicedeskapi/servicedesk/9/attachTemporaryFile",
+            ))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "temporaryAttachments": [{ "temporaryAttachmentId": "temp-1",
+                                           "fileName": "runbook.txt" }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/rest/servicedeskapi/request/OPS-1/attachment"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("runbook.txt");
+        std::fs::write(&file, "steps").unwrap();
+        // Only named, never resolved: portal_id stays 0
+        let mut config = config::create_default_config();
+        config.base_url = server.uri();
+        config.http.max_retries = 0;
+        config.portal_id = 0;
+        config.service_desk = Some("ITH".to_string());
+
+        JsmFormClient::new(config)
+            .attach_files("OPS-1", &[file])
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn dry_run_without_a_cached_schema_shows_values_as_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let payload = client(dir.path()).dry_run_submit_form(form_data()).unwrap();
+        let values = &payload.request_field_values;
+        assert_eq!(values["customfield_10878"], "2025-09-23");
+        assert_eq!(values["customfield_10900"], "medium");
+        assert_eq!(
+            values["description"],
+            "Upgrade the app\n\njsm_form idempotency key: abcdef12"
+        );
+        assert_eq!((payload.service_desk_id, payload.request_type_id), (6, 73));
+
+        let mut typo = form_data();
+        typo.fields.insert("planed_start".to_string(), json!("x"));
+        let error = client(dir.path()).dry_run_submit_form(typo).unwrap_err();
+        assert_eq!(error.exit_code(), 6);
+        assert_eq!(error.field_issues()[0].field_id, "planed_start");
+    }
 }
@@ -143,6 +143,8 @@ where
 }
 
 /// Initialize tracing subscriber with the custom formatter and environment filter support.
+///
+/// Logs are written to stderr so stdout only carries command results.
 pub fn init_logging() {
     use tracing_subscriber::EnvFilter;
 
@@ -152,86 +154,11 @@ pub fn init_logging() {
 
     tracing_subscriber::fmt()
         .with_env_filter(env_filter)
-        .event_format(FunctionFormatter::default())
+        .event_format(FunctionFormatter)
+        .with_writer(std::io::stderr)
         .init();
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Write;
-    use std::sync::{Arc, Mutex};
-    use tracing::subscriber::DefaultGuard;
-    use tracing_subscriber::fmt::MakeWriter;
-
-    #[derive(Clone, Default)]
-    struct BufferWriter {
-        buffer: Arc<Mutex<String>>,
-    }
-
-    struct BufferGuard {
-        buffer: Arc<Mutex<String>>,
-    }
-
-    impl<'a> MakeWriter<'a> for BufferWriter {
-        type Writer = BufferGuard;
-
-        fn make_writer(&'a self) -> Self::Writer {
-            BufferGuard {
-                buffer: Arc::clone(&self.buffer),
-            }
-        }
-    }
-
-    impl Write for BufferGuard {
-        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-            let mut lock = self.buffer.lock().unwrap();
-            lock.push_str(&String::from_utf8_lossy(buf));
-            Ok(buf.len())
-        }
-
-        fn flush(&mut self) -> std::io::Result<()> {
-            Ok(())
-        }
-    }
-
-    impl BufferWriter {
-        fn contents(&self) -> String {
-            self.buffer.lock().unwrap().clone()
-        }
-    }
-
-    fn install_test_subscriber() -> (BufferWriter, DefaultGuard) {
-        let writer = BufferWriter::default();
-        let subscriber = tracing_subscriber::fmt()
-            .with_writer(writer.clone())
-            .event_format(FunctionFormatter::default())
-            .finish();
-        let guard = tracing::subscriber::set_default(subscriber);
-        (writer, guard)
-    }
-
-    #[test]
-    fn function_name_includes_module_and_level() {
-        let (writer, guard) = install_test_subscriber();
-        // Emit a log using the exported macro to ensure function detection works.
-        crate::log_info!("sample message");
-        drop(guard);
-
-        let output = writer.contents();
-        assert!(output.contains("INFO"), "output was: {output:?}");
-        assert!(
-            output.contains("jsm_form::logging::tests::function_name_includes_module_and_level"),
-            "output missing module/function: {output:?}"
-        );
-        assert!(
-            output.contains("src/logging.rs:"),
-            "output missing clickable location: {output:?}"
-        );
-        assert!(output.contains("sample message"), "output missing message: {output:?}");
-    }
-}
-
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __log_function_path {
@@ -306,3 +233,79 @@ macro_rules! log_error {
         );
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing::subscriber::DefaultGuard;
+    use tracing_subscriber::fmt::MakeWriter;
+
+    #[derive(Clone, Default)]
+    struct BufferWriter {
+        buffer: Arc<Mutex<String>>,
+    }
+
+    struct BufferGuard {
+        buffer: Arc<Mutex<String>>,
+    }
+
+    impl<'a> MakeWriter<'a> for BufferWriter {
+        type Writer = BufferGuard;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            BufferGuard {
+                buffer: Arc::clone(&self.buffer),
+            }
+        }
+    }
+
+    impl Write for BufferGuard {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let mut lock = self.buffer.lock().unwrap();
+            lock.push_str(&String::from_utf8_lossy(buf));
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl BufferWriter {
+        fn contents(&self) -> String {
+            self.buffer.lock().unwrap().clone()
+        }
+    }
+
+    fn install_test_subscriber() -> (BufferWriter, DefaultGuard) {
+        let writer = BufferWriter::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(writer.clone())
+            .event_format(FunctionFormatter)
+            .finish();
+        let guard = tracing::subscriber::set_default(subscriber);
+        (writer, guard)
+    }
+
+    #[test]
+    fn function_name_includes_module_and_level() {
+        let (writer, guard) = install_test_subscriber();
+        // Emit a log using the exported macro to ensure function detection works.
+        crate::log_info!("sample message");
+        drop(guard);
+
+        let output = writer.contents();
+        assert!(output.contains("INFO"), "output was: {output:?}");
+        assert!(
+            output.contains("jsm_form::logging::tests::function_name_includes_module_and_level"),
+            "output missing module/function: {output:?}"
+        );
+        assert!(
+            output.contains("src/logging.rs:"),
+            "output missing clickable location: {output:?}"
+        );
+        assert!(output.contains("sample message"), "output missing message: {output:?}");
+    }
+}
@@ -1,41 +1,110 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use jsm_form::{FormData, JsmConfig, JsmFormClient, RiskAssessmentConfig};
-use serde_json::Value;
-use std::collections::HashMap;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use jsm_form::datetime::DateContext;
+use jsm_form::idempotency::{Idempotency, Submission};
+use jsm_form::output::{
+    AliasesOutput, AnalyzeOutput, AttachOutput, ChangeOutput, CommandOutput, ConfigFile,
+    ConfigShowOutput, ErrorOutput, FieldOutput, InitOutput, OutputFormat, ProfileSummary,
+    ProfilesOutput, RequestTypesOutput, RiskAssessmentOutput, RiskAssessmentPhase,
+    SchemaDiffOutput, ServiceDesksOutput, SubmitOutput, TemplateOutput, ValidateOutput,
+};
+use jsm_form::ticket::TicketSources;
+use jsm_form::{FormData, JsmConfig, JsmError, JsmFormClient};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "jsm_form")]
 #[command(about = "A CLI tool to automate JSM (Jira Service Management) web form completion")]
 struct Cli {
+    /// Format of the result written to stdout (logs and progress go to stderr)
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    /// Override a config value, e.g. --set http.max_retries=0 (repeatable, highest priority)
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    overrides: Vec<String>,
+    /// Apply a [profiles.<name>] section of the config (default: JSM_PROFILE)
+    #[arg(long, global = true)]
+    profile: Option<String>,
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Initialize configuration file
+    /// Initialize configuration file (interactive when run in a terminal)
     Init {
         /// Path to save the config file
         #[arg(short, long, default_value = "jsm_config.pvt.toml")]
         config: PathBuf,
+        /// Customer portal URL of the request form, e.g.
+        /// https://org.atlassian.net/servicedesk/customer/portal/6/group/12/create/73
+        #[arg(long, conflicts_with = "defaults")]
+        from_url: Option<String>,
+        /// Write a config file with placeholder values instead of asking
+        #[arg(long)]
+        defaults: bool,
+        /// Where the interactive wizard writes the ticket skeleton
+        #[arg(short, long, default_value = "ticket.toml")]
+        ticket: PathBuf,
     },
     /// Submit a form with the given data
     Submit {
         /// Path to the config file
         #[arg(short, long, default_value = "jsm_config.pvt.toml")]
         config: PathBuf,
-        /// Form data as key=value pairs
-        #[arg(short = 'd', long = "data")]
-        data: Vec<String>,
-        /// JSON file containing form data
-        #[arg(short = 'j', long = "json")]
-        json_file: Option<PathBuf>,
-        /// TOML file containing form data
-        #[arg(short = 't', long = "toml", default_value = "ticket.toml")]
-        toml_file: Option<PathBuf>,
+        #[command(flatten)]
+        ticket: TicketArgs,
+        #[command(flatten)]
+        idempotency: IdempotencyArgs,
+        /// Print the REST payload instead of submitting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Create a change request and complete its risk assessment in one run
+    Change {
+        /// Path to the config file
+        #[arg(short, long, default_value = "jsm_config.pvt.toml")]
+        config: PathBuf,
+        #[command(flatten)]
+        ticket: TicketArgs,
+        #[command(flatten)]
+        idempotency: IdempotencyArgs,
+        /// Only create the request; leave the risk assessment for later
+        #[arg(long)]
+        skip_risk_assessment: bool,
+        /// Fill in the risk assessment again even if an earlier run completed it
+        #[arg(long, conflicts_with = "skip_risk_assessment")]
+        redo_risk_assessment: bool,
+        /// Seconds to wait for the new request to become viewable
+        #[arg(long, default_value_t = 60)]
+        wait_timeout: u64,
+    },
+    /// Check a ticket file offline against the cached field schema and local rules
+    Validate {
+        /// Path to the config file
+        #[arg(short, long, default_value = "jsm_config.pvt.toml")]
+        config: PathBuf,
+        #[command(flatten)]
+        ticket: TicketArgs,
+        /// Field list to check against: a schema snapshot or `analyze --output json` output
+        /// (default: the cached snapshot of the configured request type)
+        #[arg(long)]
+        schema: Option<PathBuf>,
+    },
+    /// Attach files to an existing request
+    Attach {
+        /// Path to the config file
+        #[arg(short, long, default_value = "jsm_config.pvt.toml")]
+        config: PathBuf,
+        /// Issue key (e.g., ITH-66035)
+        #[arg(short = 'i', long = "ticket-id")]
+        ticket_id: String,
+        /// Files to attach
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
     },
     /// Complete risk assessment form for an existing ticket
     RiskAssessment {
@@ -54,15 +123,246 @@ enum Commands {
         /// Path to the config file
         #[arg(short, long, default_value = "jsm_config.pvt.toml")]
         config: PathBuf,
+        /// Print an [aliases] table generated from the field display names instead
+        #[arg(long)]
+        aliases: bool,
+        /// Write a commented ticket file with every field of the request type
+        #[arg(long, value_name = "PATH", conflicts_with = "aliases")]
+        template: Option<PathBuf>,
+        /// Show one field (by ID or display name) with all of its valid values
+        #[arg(long, conflicts_with_all = ["aliases", "template"])]
+        field: Option<String>,
+        /// Valid values listed per field in the table
+        #[arg(long, default_value_t = 5)]
+        max_values: usize,
+    },
+    /// Inspect the configuration
+    Config {
+        /// Path to the project config file
+        #[arg(short, long, global = true, default_value = "jsm_config.pvt.toml")]
+        config: PathBuf,
+        #[command(subcommand)]
+        what: ConfigCommand,
+    },
+    /// Work with the cached field schema of the request type
+    Schema {
+        /// Path to the config file
+        #[arg(short, long, global = true, default_value = "jsm_config.pvt.toml")]
+        config: PathBuf,
+        #[command(subcommand)]
+        what: SchemaCommand,
+    },
+    /// Work with the [profiles.<name>] sections of the config
+    Profiles {
+        /// Path to the config file
+        #[arg(short, long, global = true, default_value = "jsm_config.pvt.toml")]
+        config: PathBuf,
+        #[command(subcommand)]
+        what: ProfilesCommand,
+    },
+    /// List the service desks and request types available to you
+    List {
+        /// Path to the config file
+        #[arg(short, long, global = true, default_value = "jsm_config.pvt.toml")]
+        config: PathBuf,
+        #[command(subcommand)]
+        what: ListCommand,
     },
 }
 
-/// Prompt for credentials if not set in config
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Show the config layers, or with --resolved every effective value and its source
+    Show {
+        /// Print the effective values (secrets masked) and where each one comes from
+        #[arg(long)]
+        resolved: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SchemaCommand {
+    /// Compare the drift baseline with the live field list; exits with 11 on breaking changes
+    Diff {
+        /// Save the live field list as the new baseline afterwards
+        #[arg(long)]
+        update: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfilesCommand {
+    /// Profiles with the site, service desk and request type each one uses
+    List,
+}
+
+#[derive(Subcommand)]
+enum ListCommand {
+    /// Service desks visible to the configured user
+    ServiceDesks,
+    /// Request types of a service desk
+    RequestTypes {
+        /// Service desk ID, project key or name (default: the configured service desk)
+        #[arg(long)]
+        service_desk: Option<String>,
+    },
+}
+
+/// Where the fields of a ticket come from, shared by `submit`, `change` and `validate`
+#[derive(Args)]
+struct TicketArgs {
+    /// Form data as key=value pairs
+    #[arg(short = 'd', long = "data")]
+    data: Vec<String>,
+    /// JSON file containing form data
+    #[arg(short = 'j', long = "json")]
+    json_file: Option<PathBuf>,
+    /// TOML file containing form data
+    #[arg(short = 't', long = "toml", default_value = "ticket.toml")]
+    toml_file: Option<PathBuf>,
+    /// File to attach to the created request (repeatable)
+    #[arg(short = 'a', long = "attach")]
+    attachments: Vec<PathBuf>,
+    /// Raise the request on behalf of this email address or account ID
+    #[arg(long = "on-behalf-of")]
+    raise_on_behalf_of: Option<String>,
+    /// Add a request participant by email address or account ID (repeatable)
+    #[arg(long = "participant")]
+    participants: Vec<String>,
+}
+
+/// Duplicate protection of `submit` and `change`
+#[derive(Args)]
+struct IdempotencyArgs {
+    /// Idempotency key of this submission (default: derived from the merged ticket data)
+    #[arg(long)]
+    idempotency_key: Option<String>,
+    /// Also search Jira for a request created with the same idempotency key
+    #[arg(long)]
+    check_remote: bool,
+    /// Always create a new request, even if this ticket was submitted before
+    #[arg(long, conflicts_with_all = ["idempotency_key", "check_remote"])]
+    no_idempotency: bool,
+}
+
+impl IdempotencyArgs {
+    /// Idempotency settings: an explicit key, or one derived from the merged ticket data
+    /// when it comes from a ticket file
+    fn resolve(
+        &self,
+        ticket: Option<&Path>,
+        config: &JsmConfig,
+        form_data: &FormData,
+    ) -> Result<Option<Idempotency>> {
+        if self.no_idempotency {
+            return Ok(None);
+        }
+        let key = match (&self.idempotency_key, ticket) {
+            (Some(key), _) => {
+                jsm_form::idempotency::validate_key(key)?;
+                key.clone()
+            }
+            (None, Some(_)) => jsm_form::idempotency::derive_key(config, form_data)?,
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(Idempotency {
+            key,
+            state_file: jsm_form::idempotency::state_path(ticket),
+            check_remote: self.check_remote,
+        }))
+    }
+}
+
+impl TicketArgs {
+    /// The ticket or data file, which the state file is put next to
+    fn file(&self) -> Option<PathBuf> {
+        self.toml_file.clone().or_else(|| self.json_file.clone())
+    }
+
+    /// Merge the ticket sources into the form data to submit
+    fn into_form_data(self, config: &JsmConfig) -> Result<FormData> {
+        // Prepare form data: TOML, then JSON, then -d pairs (later sources win)
+        let sources = TicketSources {
+            toml_file: self.toml_file,
+            json_file: self.json_file,
+            data: self.data,
+        };
+        let date_context = DateContext::new(config.timezone.as_deref())?;
+        let mut fields = jsm_form::ticket::load_fields(&sources, &date_context)?;
+        eprintln!("Loaded {} fields", fields.len());
+
+        // Attachments listed in the ticket or data file, then any given with -a
+        let mut attachments = jsm_form::ticket::take_attachments(&mut fields, None)?;
+        attachments.extend(self.attachments);
+
+        // Reporter and participants: the command line overrides/extends the ticket file
+        let ticket_reporter = jsm_form::ticket::take_string(&mut fields, "raise_on_behalf_of")?;
+        let raise_on_behalf_of = self.raise_on_behalf_of.or(ticket_reporter);
+        let mut request_participants =
+            jsm_form::ticket::take_string_list(&mut fields, "request_participants")?;
+        request_participants.extend(self.participants);
+
+        // Remove configuration-only sections not accepted by the REST API
+        if fields.remove("risk_assessment").is_some() {
+            eprintln!(
+                "Stripped 'risk_assessment' section from submission payload (used only for risk-assessment command)"
+            );
+        }
+
+        if fields.is_empty() {
+            return Err(JsmError::Config {
+                path: None,
+                message: "no form data provided. Use -d key=value, -j data.json, or -t data.toml"
+                    .to_string(),
+            }
+            .into());
+        }
+
+        Ok(FormData {
+            attachments,
+            raise_on_behalf_of,
+            request_participants,
+            ..FormData::new(fields)
+        })
+    }
+}
+
+/// Submit once per idempotency key, or unconditionally with `--no-idempotency`
+async fn submit(
+    client: &JsmFormClient,
+    form_data: FormData,
+    idempotency: Option<&Idempotency>,
+) -> Result<Submission> {
+    Ok(match idempotency {
+        Some(idempotency) => client.submit_form_once(form_data, idempotency).await?,
+        None => Submission::Created(client.submit_form(form_data).await?),
+    })
+}
+
+/// Fill in the risk assessment with the blocking browser automation, on a blocking thread
+/// so the browser session does not hold up a runtime worker
+async fn complete_risk_assessment(
+    config: &JsmConfig,
+    ticket_id: &str,
+    risk_config: jsm_form::RiskAssessmentConfig,
+) -> jsm_form::error::Result<()> {
+    let config = config.clone();
+    let ticket_id = ticket_id.to_string();
+    tokio::task::spawn_blocking(move || {
+        jsm_form::web::complete_risk_assessment(&config, &ticket_id, &risk_config)
+    })
+    .await
+    .map_err(|error| JsmError::Other(error.into()))?
+}
+
+/// Prompt for credentials if not set in config or given by a credential helper
 fn ensure_credentials(config: &mut JsmConfig) -> Result<()> {
+    jsm_form::auth::apply_token_helper(&mut config.auth)?;
+
     // Check and prompt for username
     if config.auth.username.is_empty() || config.auth.username == "your-username" {
-        print!("Enter username: ");
-        io::stdout().flush()?;
+        eprint!("Enter username: ");
+        io::stderr().flush()?;
         let mut username = String::new();
         io::stdin().read_line(&mut username)?;
         config.auth.username = username.trim().to_string();
@@ -73,10 +373,7 @@ fn ensure_credentials(config: &mut JsmConfig) -> Result<()> {
     }
 
     // Check and prompt for Atlassian API token
-    if config.auth.token_atlassian_api.is_empty()
-        || config.auth.token_atlassian_api == "your-api-token-here"
-        || config.auth.token_atlassian_api == "your-password"
-    {
+    if jsm_form::auth::is_unset(&config.auth.token_atlassian_api) {
         let token = rpassword::prompt_password("Enter Atlassian API token: ")?;
         if token.is_empty() {
             return Err(anyhow::anyhow!("API token cannot be empty"));
@@ -97,130 +394,512 @@ fn ensure_credentials(config: &mut JsmConfig) -> Result<()> {
     //     config.auth.microsoft_password = password;
     // }
 
-    println!("Credentials configured for user: {}", config.auth.username);
+    eprintln!("Credentials configured for user: {}", config.auth.username);
     Ok(())
 }
 
+/// Ask a question on stderr and read the answer, falling back to `default` when empty
+fn prompt(question: &str, default: Option<&str>) -> Result<String> {
+    match default {
+        Some(default) => eprint!("{} [{}]: ", question, default),
+        None => eprint!("{}: ", question),
+    }
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        anyhow::bail!("No answer to '{}' (end of input)", question);
+    }
+    let answer = answer.trim();
+    Ok(match default {
+        Some(default) if answer.is_empty() => default.to_string(),
+        _ => answer.to_string(),
+    })
+}
+
+/// Ask a yes/no question; anything but y/yes means no
+fn confirm(question: &str) -> Result<bool> {
+    let answer = prompt(&format!("{} (y/N)", question), None)?;
+    Ok(matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Show a numbered table and let the user pick a row by number or by `find`
+fn pick<'a, T>(
+    what: &str,
+    headers: &[&str],
+    items: &'a [T],
+    row: impl Fn(&T) -> Vec<String>,
+    find: impl Fn(&str, &'a [T]) -> std::result::Result<&'a T, String>,
+) -> Result<&'a T> {
+    if items.is_empty() {
+        anyhow::bail!("No {} available to this account", what);
+    }
+    let mut table_headers = vec!["#"];
+    table_headers.extend_from_slice(headers);
+    let rows: Vec<Vec<String>> = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let mut cells = vec![(index + 1).to_string()];
+            cells.extend(row(item));
+            cells
+        })
+        .collect();
+    eprintln!("{}", jsm_form::table::render(&table_headers, &rows));
+
+    loop {
+        let default = (items.len() == 1).then_some("1");
+        let answer = prompt(&format!("Choose a {} (number or name)", what), default)?;
+        let chosen = match answer.parse::<usize>() {
+            Ok(number) if (1..=items.len()).contains(&number) => Ok(&items[number - 1]),
+            _ => find(&answer, items),
+        };
+        match chosen {
+            Ok(item) => return Ok(item),
+            Err(message) => eprintln!("{}", message),
+        }
+    }
+}
+
+/// Interactive `init`: ask for the site and credentials, verify them, pick the service
+/// desk and request type, then write the config file and a ticket skeleton
+async fn init_wizard(config_path: PathBuf, ticket_path: PathBuf) -> Result<InitOutput> {
+    if config_path.exists()
+        && !confirm(&format!("{} exists. Overwrite it?", config_path.display()))?
+    {
+        anyhow::bail!("Not overwriting {}", config_path.display());
+    }
+
+    let site = prompt("Atlassian site (e.g. acme or acme.atlassian.net)", None)?;
+    let mut config = jsm_form::config::config_for_site(&site)?;
+    config.auth.username = prompt("Atlassian account email", None)?;
+    ensure_credentials(&mut config)?;
+
+    let client = JsmFormClient::new(config.clone());
+    eprintln!("Checking credentials against {}...", config.base_url);
+    client.authenticate().await?;
+    eprintln!("Authentication successful!");
+
+    let desks = client.service_desks().await?;
+    let desk = pick(
+        "service desk",
+        &["ID", "Key", "Name"],
+        &desks,
+        |desk| {
+            vec![
+                desk.id.clone(),
+                desk.project_key.clone(),
+                desk.project_name.clone(),
+            ]
+        },
+        jsm_form::discovery::find_service_desk,
+    )?;
+    config.portal_id = desk
+        .id
+        .parse()
+        .with_context(|| format!("Unexpected service desk ID '{}'", desk.id))?;
+
+    let request_types = client.request_types(&desk.id).await?;
+    let request_type = pick(
+        "request type",
+        &["ID", "Name", "Description"],
+        &request_types,
+        |request_type| {
+            vec![
+                request_type.id.clone(),
+                request_type.name.clone(),
+                request_type.description.clone(),
+            ]
+        },
+        jsm_form::discovery::find_request_type,
+    )?;
+    config.request_type_id = request_type
+        .id
+        .parse()
+        .with_context(|| format!("Unexpected request type ID '{}'", request_type.id))?;
+
+    let fields = JsmFormClient::new(config.clone())
+        .request_type_fields()
+        .await?;
+
+    let mut saved = config.clone();
+    if !confirm(&format!(
+        "Save the API token in {}? Otherwise you are asked for it on every run",
+        config_path.display()
+    ))? {
+        saved.auth.token_atlassian_api.clear();
+    }
+    jsm_form::config::save_config(&saved, &config_path)?;
+
+    let ticket_path = if !ticket_path.exists()
+        || confirm(&format!("{} exists. Overwrite it?", ticket_path.display()))?
+    {
+        let template = jsm_form::template::render_ticket_template(request_type, &fields);
+        std::fs::write(&ticket_path, template)
+            .with_context(|| format!("Failed to write {}", ticket_path.display()))?;
+        Some(ticket_path)
+    } else {
+        None
+    };
+
+    Ok(InitOutput {
+        config_path,
+        ticket_path,
+    })
+}
+
+/// Write a command result to stdout in the selected format
+fn emit<T: CommandOutput>(format: OutputFormat, output: &T) -> Result<()> {
+    let rendered =
+        jsm_form::output::render(format, output).context("Failed to render command output")?;
+    // NDJSON without records is no output at all, not an empty line
+    if !rendered.is_empty() {
+        println!("{}", rendered);
+    }
+    Ok(())
+}
+
+/// Exit code of `change` when the request was created but the risk assessment failed
+const EXIT_INCOMPLETE: u8 = 10;
+
+/// Exit code of `validate` when the ticket has problems, the same as a rejected submission
+const EXIT_INVALID_TICKET: u8 = 6;
+
+/// Exit code of `schema diff` when the live schema has breaking changes
+const EXIT_SCHEMA_DRIFT: u8 = 11;
+
+/// Map an error to the documented exit code of its `JsmError` variant
+fn exit_code(error: &anyhow::Error) -> u8 {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<JsmError>())
+        .map_or(1, JsmError::exit_code)
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     // Initialize tracing
     jsm_form::logging::init_logging();
 
     let cli = Cli::parse();
+    let format = cli.output;
+    match run(cli).await {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("Error: {:?}", error);
+            let code = exit_code(&error);
+            // Scripts reading stdout get a JSON error in place of the result
+            if format != OutputFormat::Text
+                && let Err(emit_error) = emit(format, &ErrorOutput::new(&error, code))
+            {
+                eprintln!("{:#}", emit_error);
+            }
+            ExitCode::from(code)
+        }
+    }
+}
+
+/// Run the command; commands that finish with a non-error status return its exit code
+async fn run(cli: Cli) -> Result<ExitCode> {
+    let format = cli.output;
+    let mut overrides = cli.overrides;
+    // --profile is the same as --set profile=<name>, so it wins over JSM_PROFILE
+    if let Some(profile) = cli.profile {
+        overrides.push(format!("profile={}", profile));
+    }
+    // Defaults, system, user and project files, the selected profile, JSM_* variables, then --set
+    let config_sources =
+        |config: &Path| jsm_form::config::ConfigSources::new(config).with_overrides(&overrides);
+    let load_config = |config: &Path| -> Result<JsmConfig> {
+        Ok(jsm_form::config::load_layered(&config_sources(config)?)?)
+    };
 
     match cli.command {
-        Commands::Init { config } => {
-            let default_config = jsm_form::config::create_default_config();
-            jsm_form::config::save_config(&default_config, &config)?;
-            println!("Configuration file created at: {}", config.display());
-            println!("Please edit the file with your credentials and settings.");
+        Commands::Init {
+            config,
+            from_url,
+            defaults,
+            ticket,
+        } => {
+            if from_url.is_none() && !defaults && io::stdin().is_terminal() {
+                let output = init_wizard(config, ticket).await?;
+                emit(format, &output)?;
+                return Ok(ExitCode::SUCCESS);
+            }
+            let new_config = match from_url {
+                None => jsm_form::config::create_default_config(),
+                Some(portal_url) => {
+                    let mut new_config = jsm_form::config::config_from_portal_url(&portal_url)?;
+                    eprintln!(
+                        "Checking service desk {} and request type {} on {}...",
+                        new_config.portal_id, new_config.request_type_id, new_config.base_url
+                    );
+                    let mut verify_config = new_config.clone();
+                    ensure_credentials(&mut verify_config)?;
+                    let client = JsmFormClient::new(verify_config.clone());
+                    client.authenticate().await?;
+                    let request_type = client.request_type().await?;
+                    eprintln!("Found request type: {}", request_type.name);
+                    // Keep the username, but never write the token to disk
+                    new_config.auth.username = verify_config.auth.username;
+                    new_config
+                }
+            };
+            jsm_form::config::save_config(&new_config, &config)?;
+            emit(
+                format,
+                &InitOutput {
+                    config_path: config,
+                    ticket_path: None,
+                },
+            )?;
         }
 
         Commands::Submit {
             config,
-            data,
-            json_file,
-            toml_file,
+            ticket,
+            idempotency,
+            dry_run,
         } => {
-            let mut config = jsm_form::config::load_config(&config)?;
+            let mut config = load_config(&config)?;
+
+            if dry_run {
+                if config.service_desk.is_some() || config.request_type.is_some() {
+                    jsm_form::log_warn!(
+                        "service_desk/request_type names are only resolved when submitting; the dry run uses portal_id/request_type_id as configured"
+                    );
+                }
+                let ticket_file = ticket.file();
+                let mut form_data = ticket.into_form_data(&config)?;
+                let idempotency =
+                    idempotency.resolve(ticket_file.as_deref(), &config, &form_data)?;
+                if let Some(idempotency) = &idempotency {
+                    eprintln!("Idempotency key: {}", idempotency.key);
+                    form_data.idempotency_key = Some(idempotency.key.clone());
+                }
+                for attachment in &form_data.attachments {
+                    eprintln!("Would attach: {}", attachment.display());
+                }
+                let attachments = form_data.attachments.clone();
+                let payload = JsmFormClient::new(config).dry_run_submit_form(form_data)?;
+                emit(
+                    format,
+                    &SubmitOutput::DryRun {
+                        payload,
+                        attachments,
+                    },
+                )?;
+                return Ok(ExitCode::SUCCESS);
+            }
 
             // Ensure credentials are provided
             ensure_credentials(&mut config)?;
 
-            let client = JsmFormClient::new(config);
+            let mut client = JsmFormClient::new(config);
 
             // Authenticate first
-            println!("main.rs:Submit Authenticating...");
+            eprintln!("main.rs:Submit Authenticating...");
             client.authenticate().await?;
-            println!("Authentication successful!");
+            eprintln!("Authentication successful!");
+            client.resolve_target().await?;
 
-            // Prepare form data
-            let mut fields: HashMap<String, Value> = HashMap::new();
+            let ticket_file = ticket.file();
+            let form_data = ticket.into_form_data(client.config())?;
+            let idempotency =
+                idempotency.resolve(ticket_file.as_deref(), client.config(), &form_data)?;
 
-            // Load from TOML file if provided
-            if let Some(toml_path) = toml_file {
-                let toml_content = std::fs::read_to_string(&toml_path).with_context(|| {
-                    format!("Failed to read TOML file: {}", toml_path.display())
-                })?;
+            eprintln!("Submitting form with {} fields...", form_data.fields.len());
+            let submission = submit(&client, form_data, idempotency.as_ref()).await?;
+            eprintln!("Form submitted successfully!");
+            emit(format, &SubmitOutput::from(submission))?;
+        }
 
-                // Parse as TOML value first, then convert to JSON value
-                let toml_value: toml::Value = toml::from_str(&toml_content).with_context(|| {
-                    format!("Failed to parse TOML file: {}", toml_path.display())
-                })?;
+        Commands::Change {
+            config,
+            ticket,
+            idempotency,
+            skip_risk_assessment,
+            redo_risk_assessment,
+            wait_timeout,
+        } => {
+            let mut config = load_config(&config)?;
 
-                // Convert TOML value to JSON value
-                let json_string =
-                    serde_json::to_string(&toml_value).context("Failed to convert TOML to JSON")?;
-                let json_value: Value =
-                    serde_json::from_str(&json_string).context("Failed to parse converted JSON")?;
-
-                if let Value::Object(map) = json_value {
-                    fields.extend(map);
-                    println!(
-                        "Loaded {} fields from TOML file: {}",
-                        fields.len(),
-                        toml_path.display()
-                    );
-                } else {
-                    return Err(anyhow::anyhow!(
-                        "TOML file must contain an object at the root level"
-                    ));
-                }
+            // Read the risk assessment up front so a bad section fails before anything is created
+            let risk_config = if skip_risk_assessment {
+                None
+            } else {
+                let toml_file = ticket
+                    .toml_file
+                    .clone()
+                    .context("The risk assessment is read from the TOML ticket file (-t); pass one or use --skip-risk-assessment")?;
+                Some(jsm_form::ticket::load_risk_assessment(&toml_file)?)
+            };
+
+            // Ensure credentials are provided; the Microsoft password only for the browser
+            ensure_credentials(&mut config)?;
+            if risk_config.is_some() {
+                jsm_form::auth::apply_microsoft_password_helper(&mut config.auth)?;
             }
 
-            // Load from JSON file if provided (will override TOML fields with same keys)
-            if let Some(json_path) = json_file {
-                let json_content = std::fs::read_to_string(&json_path).with_context(|| {
-                    format!("Failed to read JSON file: {}", json_path.display())
-                })?;
-                let json_value: Value = serde_json::from_str(&json_content).with_context(|| {
-                    format!("Failed to parse JSON file: {}", json_path.display())
-                })?;
+            let mut client = JsmFormClient::new(config);
+            eprintln!("main.rs:Change Authenticating...");
+            client.authenticate().await?;
+            eprintln!("Authentication successful!");
+            client.resolve_target().await?;
 
-                if let Value::Object(map) = json_value {
-                    let json_field_count = map.len();
-                    fields.extend(map);
-                    println!(
-                        "Loaded {} additional fields from JSON file: {}",
-                        json_field_count,
-                        json_path.display()
-                    );
-                } else {
-                    return Err(anyhow::anyhow!(
-                        "JSON file must contain an object at the root level"
-                    ));
+            let ticket_file = ticket.file();
+            let form_data = ticket.into_form_data(client.config())?;
+            let idempotency =
+                idempotency.resolve(ticket_file.as_deref(), client.config(), &form_data)?;
+
+            eprintln!("Submitting form with {} fields...", form_data.fields.len());
+            let submission = submit(&client, form_data, idempotency.as_ref()).await?;
+            let existing = match &submission {
+                Submission::Created(_) => None,
+                Submission::Existing { source, .. } => Some(*source),
+            };
+            let created = submission.request().clone();
+            eprintln!("Using request {}", created.issue_key);
+
+            // An earlier run may have completed the risk assessment of an existing request
+            let already_completed = match &idempotency {
+                Some(idempotency) if existing.is_some() && !redo_risk_assessment => {
+                    jsm_form::idempotency::risk_assessment_completed(idempotency)?
                 }
+                _ => false,
+            };
+
+            // From here on the request exists: report its key whatever happens next
+            let risk_assessment = match risk_config {
+                None => RiskAssessmentPhase::Skipped,
+                Some(_) if already_completed => RiskAssessmentPhase::AlreadyCompleted,
+                Some(risk_config) => {
+                    let result = async {
+                        client
+                            .wait_until_viewable(
+                                &created.issue_key,
+                                Duration::from_secs(wait_timeout),
+                            )
+                            .await?;
+                        eprintln!(
+                            "Completing risk assessment for ticket: {}",
+                            created.issue_key
+                        );
+                        complete_risk_assessment(client.config(), &created.issue_key, risk_config)
+                            .await
+                    }
+                    .await;
+                    match result {
+                        Ok(()) => {
+                            if let Some(idempotency) = &idempotency
+                                && let Err(error) =
+                                    jsm_form::idempotency::record_risk_assessment(idempotency)
+                            {
+                                jsm_form::log_warn!(
+                                    "Could not record the risk assessment in {}: {:#}",
+                                    idempotency.state_file.display(),
+                                    error
+                                );
+                            }
+                            RiskAssessmentPhase::Completed
+                        }
+                        Err(error) => {
+                            jsm_form::log_error!(
+                                "Risk assessment for {} failed: {:#}",
+                                created.issue_key,
+                                error
+                            );
+                            RiskAssessmentPhase::Failed {
+                                error: format!("{:#}", error),
+                            }
+                        }
+                    }
+                }
+            };
+
+            let output = ChangeOutput {
+                request: created,
+                existing,
+                risk_assessment,
+            };
+            emit(format, &output)?;
+            if !output.succeeded() {
+                return Ok(ExitCode::from(EXIT_INCOMPLETE));
             }
+        }
 
-            // Add command line data (will override file fields with same keys)
-            for item in data {
-                if let Some((key, value)) = item.split_once('=') {
-                    fields.insert(key.to_string(), Value::String(value.to_string()));
-                } else {
-                    eprintln!(
-                        "Warning: Invalid data format '{}', expected 'key=value'",
-                        item
+        Commands::Validate {
+            config,
+            ticket,
+            schema,
+        } => {
+            let config = load_config(&config)?;
+            let schema = match schema {
+                Some(path) => Some(jsm_form::snapshot::load_fields_file(&path)?),
+                None if config.portal_id == 0 || config.request_type_id == 0 => {
+                    jsm_form::log_warn!(
+                        "service_desk/request_type names cannot be resolved offline; set portal_id/request_type_id or pass --schema to check fields"
                     );
+                    None
                 }
-            }
+                None => match jsm_form::snapshot::load_snapshot(&config)? {
+                    Some(snapshot) => {
+                        eprintln!("Using field schema fetched at {}", snapshot.fetched_at);
+                        Some(snapshot.fields)
+                    }
+                    None => {
+                        jsm_form::log_warn!(
+                            "No cached field schema at {}; run `analyze` or `schema diff` once to cache it. Only local rules are checked.",
+                            jsm_form::snapshot::snapshot_path(&config).display()
+                        );
+                        None
+                    }
+                },
+            };
 
-            // Remove configuration-only sections not accepted by the REST API
-            if fields.remove("risk_assessment").is_some() {
-                println!(
-                    "Stripped 'risk_assessment' section from submission payload (used only for risk-assessment command)"
-                );
+            let mut issues = match &ticket.toml_file {
+                Some(path) => jsm_form::validate::check_risk_assessment(path)?,
+                None => Vec::new(),
+            };
+            let form_data = ticket.into_form_data(&config)?;
+            issues.extend(jsm_form::validate::validate_ticket(
+                &config,
+                form_data,
+                schema.as_ref(),
+            )?);
+
+            let output = ValidateOutput {
+                valid: issues.is_empty(),
+                schema_checked: schema.is_some(),
+                issues,
+            };
+            emit(format, &output)?;
+            if !output.valid {
+                return Ok(ExitCode::from(EXIT_INVALID_TICKET));
             }
+        }
+
+        Commands::Attach {
+            config,
+            ticket_id,
+            files,
+        } => {
+            let mut config = load_config(&config)?;
 
-            let form_data = FormData { fields };
+            // Ensure credentials are provided
+            ensure_credentials(&mut config)?;
 
-            if form_data.fields.is_empty() {
-                eprintln!("No form data provided. Use -d key=value, -j data.json, or -t data.toml");
-                std::process::exit(1);
-            }
+            let client = JsmFormClient::new(config);
+            client.authenticate().await?;
 
-            println!("Submitting form with {} fields...", form_data.fields.len());
-            client.submit_form(form_data).await?;
-            println!("Form submitted successfully!");
+            eprintln!("Attaching {} file(s) to {}...", files.len(), ticket_id);
+            client.attach_files(&ticket_id, &files).await?;
+            emit(
+                format,
+                &AttachOutput {
+                    ticket_id,
+                    attached: files,
+                },
+            )?;
         }
 
         Commands::RiskAssessment {
@@ -228,114 +907,259 @@ async fn main() -> Result<()> {
             ticket_id,
             toml_file,
         } => {
-            let mut config = jsm_form::config::load_config(&config)?;
+            let mut config = load_config(&config)?;
 
             // Ensure credentials are provided
             ensure_credentials(&mut config)?;
+            jsm_form::auth::apply_microsoft_password_helper(&mut config.auth)?;
 
             // Load risk assessment configuration from TOML file
-            let toml_content = std::fs::read_to_string(&toml_file)
-                .with_context(|| format!("Failed to read TOML file: {}", toml_file.display()))?;
-
-            // Parse the entire TOML file first
-            let toml_value: toml::Value = toml::from_str(&toml_content)
-                .with_context(|| format!("Failed to parse TOML file: {}", toml_file.display()))?;
-
-            // Extract the risk_assessment section
-            let risk_assessment_section = toml_value
-                .get("risk_assessment")
-                .context("Missing 'risk_assessment' section in TOML file")?;
-
-            // Convert the risk_assessment section to RiskAssessmentConfig
-            let risk_config: RiskAssessmentConfig = risk_assessment_section
-                .clone()
-                .try_into()
-                .with_context(|| {
-                    format!(
-                        "Failed to parse risk assessment configuration from TOML file: {}",
-                        toml_file.display()
-                    )
-                })?;
+            let risk_config = jsm_form::ticket::load_risk_assessment(&toml_file)?;
 
-            println!("Completing risk assessment for ticket: {}", ticket_id);
-            jsm_form::web::complete_risk_assessment(&config, &ticket_id, &risk_config)?;
-            println!("Risk assessment completed successfully!");
+            eprintln!("Completing risk assessment for ticket: {}", ticket_id);
+            complete_risk_assessment(&config, &ticket_id, risk_config).await?;
+            emit(
+                format,
+                &RiskAssessmentOutput {
+                    ticket_id,
+                    completed: true,
+                },
+            )?;
         }
 
-        Commands::Analyze { config } => {
-            let mut config = jsm_form::config::load_config(&config)?;
+        Commands::Analyze {
+            config,
+            aliases,
+            template,
+            field,
+            max_values,
+        } => {
+            let mut config = load_config(&config)?;
 
             // Ensure credentials are provided
             ensure_credentials(&mut config)?;
 
-            let client = JsmFormClient::new(config.clone());
+            let mut client = JsmFormClient::new(config);
 
-            println!("main.rs:Analyze Authenticating...");
+            eprintln!("main.rs:Analyze Authenticating...");
             client.authenticate().await?;
-            println!("Authentication successful!");
+            eprintln!("Authentication successful!");
+            client.resolve_target().await?;
+
+            if aliases {
+                let schema = client.request_type_fields().await?;
+                let table = jsm_form::aliases::generate_alias_table(&schema);
+                let toml = jsm_form::aliases::render_alias_table(&table, &schema);
+                emit(
+                    format,
+                    &AliasesOutput {
+                        aliases: table,
+                        toml,
+                    },
+                )?;
+                return Ok(ExitCode::SUCCESS);
+            }
 
-            println!(
+            if let Some(path) = template {
+                if path.exists() {
+                    anyhow::bail!(
+                        "{} already exists; remove it or choose another path",
+                        path.display()
+                    );
+                }
+                let analysis = client.analyze().await?;
+                let contents = jsm_form::template::render_ticket_template(
+                    &analysis.request_type,
+                    &analysis.fields,
+                );
+                std::fs::write(&path, contents)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                emit(
+                    format,
+                    &TemplateOutput {
+                        template_path: path,
+                        request_type: analysis.request_type.name,
+                        fields: analysis
+                            .fields
+                            .request_type_fields
+                            .iter()
+                            .filter(|field| field.visible)
+                            .count(),
+                    },
+                )?;
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            eprintln!(
                 "Analyzing form structure for service desk {} and request type {}...",
-                config.portal_id, config.request_type_id
+                client.config().portal_id,
+                client.config().request_type_id
             );
+            let analysis = client.analyze().await?;
 
-            // Get request type details to understand the required fields
-            let request_type_url = format!(
-                "{}/rest/servicedeskapi/servicedesk/{}/requesttype/{}",
-                config.base_url, config.portal_id, config.request_type_id
-            );
+            if let Some(wanted) = field {
+                let field = analysis.find_field(&wanted).cloned().with_context(|| {
+                    format!(
+                        "Unknown field '{}'; run analyze without --field to list the fields",
+                        wanted
+                    )
+                })?;
+                emit(format, &FieldOutput { field })?;
+                return Ok(ExitCode::SUCCESS);
+            }
+            emit(
+                format,
+                &AnalyzeOutput {
+                    analysis,
+                    max_values,
+                },
+            )?;
+        }
 
-            println!("Fetching request type details from: {}", request_type_url);
-
-            let response = reqwest::Client::new()
-                .get(&request_type_url)
-                .basic_auth(
-                    &config.auth.username,
-                    Some(&config.auth.token_atlassian_api),
-                )
-                .header("Accept", "application/json")
-                .send()
-                .await?;
-
-            if response.status().is_success() {
-                let body = response.text().await?;
-                println!("Request type details:");
-                println!("{}", body);
+        Commands::Config { config, what } => {
+            let ConfigCommand::Show { resolved } = what;
+            let sources = config_sources(&config)?;
+            let values = if resolved {
+                Some(jsm_form::config::resolve_with_sources(&sources)?)
             } else {
-                println!("Failed to get request type details: {}", response.status());
-                let error_body = response.text().await.unwrap_or_default();
-                println!("Error: {}", error_body);
-            }
+                None
+            };
+            emit(
+                format,
+                &ConfigShowOutput {
+                    files: sources
+                        .files()
+                        .into_iter()
+                        .map(|(layer, path)| ConfigFile {
+                            layer: layer.to_string(),
+                            path: path.to_path_buf(),
+                            exists: path.is_file(),
+                        })
+                        .collect(),
+                    values,
+                },
+            )?;
+        }
 
-            // Also try to get field information
-            let fields_url = format!(
-                "{}/rest/servicedeskapi/servicedesk/{}/requesttype/{}/field",
-                config.base_url, config.portal_id, config.request_type_id
-            );
+        Commands::Schema { config, what } => {
+            let SchemaCommand::Diff { update } = what;
+            let mut config = load_config(&config)?;
+
+            // Ensure credentials are provided
+            ensure_credentials(&mut config)?;
 
-            println!("\nFetching field details from: {}", fields_url);
-
-            let fields_response = reqwest::Client::new()
-                .get(&fields_url)
-                .basic_auth(
-                    &config.auth.username,
-                    Some(&config.auth.token_atlassian_api),
-                )
-                .header("Accept", "application/json")
-                .send()
-                .await?;
-
-            if fields_response.status().is_success() {
-                let fields_body = fields_response.text().await?;
-                println!("Available fields:");
-                println!("{}", fields_body);
+            let mut client = JsmFormClient::new(config);
+            client.authenticate().await?;
+            client.resolve_target().await?;
+
+            let baseline = jsm_form::snapshot::load_baseline(client.config())?;
+            let live = client.live_request_type_fields().await?;
+            let diff = match &baseline {
+                Some(baseline) => jsm_form::snapshot::diff(&baseline.fields, &live),
+                None => Default::default(),
+            };
+            let breaking = diff.is_breaking();
+            let updated = update || baseline.is_none();
+            let baseline_path = if updated {
+                jsm_form::snapshot::save_baseline(
+                    client.config(),
+                    &jsm_form::snapshot::SchemaSnapshot::new(client.config(), live),
+                )?
             } else {
-                println!("Failed to get field details: {}", fields_response.status());
-                let fields_error = fields_response.text().await.unwrap_or_default();
-                println!("Error: {}", fields_error);
+                jsm_form::snapshot::baseline_path(client.config())
+            };
+
+            emit(
+                format,
+                &SchemaDiffOutput {
+                    baseline_path,
+                    baseline_fetched_at: baseline.map(|baseline| baseline.fetched_at),
+                    diff,
+                    breaking,
+                    updated,
+                },
+            )?;
+            if breaking {
+                return Ok(ExitCode::from(EXIT_SCHEMA_DRIFT));
+            }
+        }
+
+        Commands::Profiles { config, what } => {
+            let ProfilesCommand::List = what;
+            let sources = config_sources(&config)?;
+            let base = jsm_form::config::load_layered_base(&sources)?;
+            let profiles = base
+                .profiles
+                .keys()
+                .map(|name| {
+                    let config = jsm_form::config::load_layered(
+                        &sources
+                            .clone()
+                            .with_overrides(&[format!("profile={}", name)])?,
+                    )?;
+                    Ok(ProfileSummary {
+                        name: name.clone(),
+                        active: base
+                            .profile
+                            .as_deref()
+                            .is_some_and(|active| active.eq_ignore_ascii_case(name)),
+                        base_url: config.base_url,
+                        service_desk: config
+                            .service_desk
+                            .unwrap_or_else(|| config.portal_id.to_string()),
+                        request_type: config
+                            .request_type
+                            .unwrap_or_else(|| config.request_type_id.to_string()),
+                        username: config.auth.username,
+                    })
+                })
+                .collect::<Result<_>>()?;
+            emit(format, &ProfilesOutput { profiles })?;
+        }
+
+        Commands::List { config, what } => {
+            let mut config = load_config(&config)?;
+
+            // Ensure credentials are provided
+            ensure_credentials(&mut config)?;
+
+            let client = JsmFormClient::new(config.clone());
+            client.authenticate().await?;
+
+            match what {
+                ListCommand::ServiceDesks => {
+                    let service_desks = client.service_desks().await?;
+                    emit(format, &ServiceDesksOutput { service_desks })?;
+                }
+                ListCommand::RequestTypes { service_desk } => {
+                    let wanted = service_desk
+                        .or(config.service_desk)
+                        .or_else(|| (config.portal_id != 0).then(|| config.portal_id.to_string()))
+                        .context(
+                            "No service desk configured; pass --service-desk <id|project key>",
+                        )?;
+                    // Numeric IDs are used as-is; keys and names are looked up
+                    let service_desk_id = if wanted.chars().all(|c| c.is_ascii_digit()) {
+                        wanted
+                    } else {
+                        let desks = client.service_desks().await?;
+                        jsm_form::discovery::find_service_desk(&wanted, &desks)
+                            .map_err(anyhow::Error::msg)?
+                            .id
+                            .clone()
+                    };
+                    let request_types = client.request_types(&service_desk_id).await?;
+                    emit(
+                        format,
+                        &RequestTypesOutput {
+                            service_desk_id,
+                            request_types,
+                        },
+                    )?;
+                }
             }
         }
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
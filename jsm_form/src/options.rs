@@ -0,0 +1,188 @@
+use crate::schema::{FieldIssue, FieldValue, IssueKind, RequestTypeField, RequestTypeFields};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+/// Minimum similarity for an option to be offered as a "did you mean" suggestion
+const SUGGESTION_THRESHOLD: f64 = 0.5;
+
+/// Translate display strings of select, radio and multi-select fields into option objects.
+///
+/// A string matching an option label (case-insensitive) or option ID becomes
+/// `{"id": "<option id>"}`. Objects are left untouched. Values that match no option
+/// are reported with the closest valid option as a suggestion.
+pub fn resolve_option_values(
+    fields: &mut BTreeMap<String, Value>,
+    schema: &RequestTypeFields,
+) -> Vec<FieldIssue> {
+    let mut issues = Vec::new();
+
+    for (field_id, value) in fields.iter_mut() {
+        let Some(field) = schema.field(field_id) else {
+            continue;
+        };
+        if field.valid_values.is_empty() {
+            continue;
+        }
+
+        let single = field.jira_schema.field_type == "option";
+        let multi = field.jira_schema.field_type == "array"
+            && field.jira_schema.items.as_deref() == Some("option");
+
+        if single {
+            if let Err(message) = resolve_single(field, value) {
+                issues.push(option_issue(field, message));
+            }
+        } else if multi {
+            if let Value::Array(items) = value {
+                for item in items.iter_mut() {
+                    if let Err(message) = resolve_single(field, item) {
+                        issues.push(option_issue(field, message));
+                    }
+                }
+            } else if value.is_string() {
+                // A single display string for a multi-select means one selected option
+                let mut item = value.take();
+                match resolve_single(field, &mut item) {
+                    Ok(()) => *value = Value::Array(vec![item]),
+                    Err(message) => {
+                        *value = item;
+                        issues.push(option_issue(field, message));
+                    }
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+fn option_issue(field: &RequestTypeField, message: String) -> FieldIssue {
+    FieldIssue {
+        field_id: field.field_id.clone(),
+        field_name: Some(field.name.clone()),
+        kind: IssueKind::UnknownOption,
+        message,
+    }
+}
+
+/// Replace a display string with the matching option object
+fn resolve_single(field: &RequestTypeField, value: &mut Value) -> Result<(), String> {
+    let Value::String(text) = value else {
+        return Ok(());
+    };
+    let wanted = text.trim();
+
+    let found = field
+        .valid_values
+        .iter()
+        .find(|option| option.label.trim().eq_ignore_ascii_case(wanted))
+        .or_else(|| {
+            field
+                .valid_values
+                .iter()
+                .find(|option| option.value == wanted)
+        });
+
+    match found {
+        Some(option) => {
+            *value = json!({ "id": option.value });
+            Ok(())
+        }
+        None => Err(unknown_option_message(wanted, &field.valid_values)),
+    }
+}
+
+fn unknown_option_message(wanted: &str, options: &[FieldValue]) -> String {
+    let labels: Vec<&str> = options.iter().map(|option| option.label.as_str()).collect();
+    let wanted_lower = wanted.to_lowercase();
+    let suggestion = labels
+        .iter()
+        .map(|label| {
+            (
+                strsim::normalized_damerau_levenshtein(&wanted_lower, &label.to_lowercase()),
+                *label,
+            )
+        })
+        .filter(|(score, _)| *score >= SUGGESTION_THRESHOLD)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, label)| format!(" Did you mean '{}'?", label))
+        .unwrap_or_default();
+
+    format!(
+        "'{}' is not a valid option.{} Valid options: {}",
+        wanted,
+        suggestion,
+        labels.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> RequestTypeFields {
+        serde_json::from_value(json!({
+            "requestTypeFields": [
+                {
+                    "fieldId": "customfield_10900",
+                    "name": "Impact",
+                    "validValues": [
+                        { "value": "10001", "label": "Low" },
+                        { "value": "10002", "label": "Medium" },
+                        { "value": "10003", "label": "High" }
+                    ],
+                    "jiraSchema": { "type": "option" }
+                },
+                {
+                    "fieldId": "customfield_10901",
+                    "name": "Environments",
+                    "validValues": [
+                        { "value": "20001", "label": "Production" },
+                        { "value": "20002", "label": "Staging" }
+                    ],
+                    "jiraSchema": { "type": "array", "items": "option" }
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn resolves_labels_and_ids_to_option_objects() {
+        let mut fields = BTreeMap::from([
+            ("customfield_10900".to_string(), json!("medium")),
+            (
+                "customfield_10901".to_string(),
+                json!(["Production", "20002", { "id": "20001" }]),
+            ),
+        ]);
+        let issues = resolve_option_values(&mut fields, &schema());
+        assert!(issues.is_empty(), "{issues:?}");
+        assert_eq!(fields["customfield_10900"], json!({ "id": "10002" }));
+        assert_eq!(
+            fields["customfield_10901"],
+            json!([{ "id": "20001" }, { "id": "20002" }, { "id": "20001" }])
+        );
+    }
+
+    #[test]
+    fn suggests_closest_option_on_mismatch() {
+        let mut fields = BTreeMap::from([
+            ("customfield_10900".to_string(), json!("Meduim")),
+            ("customfield_10901".to_string(), json!("Prod")),
+        ]);
+        let issues = resolve_option_values(&mut fields, &schema());
+        assert_eq!(issues.len(), 2);
+        assert!(issues.iter().all(FieldIssue::depends_on_field_list));
+        assert_eq!(
+            issues[0].message,
+            "'Meduim' is not a valid option. Did you mean 'Medium'? Valid options: Low, Medium, High"
+        );
+        assert!(
+            issues[1]
+                .message
+                .starts_with("'Prod' is not a valid option.")
+        );
+        assert_eq!(fields["customfield_10900"], json!("Meduim"));
+    }
+}
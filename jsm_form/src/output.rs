@@ -0,0 +1,946 @@
+use crate::config::ResolvedValue;
+use crate::discovery::ServiceDesk;
+use crate::form::{CreateRequestPayload, CreatedRequest};
+use crate::idempotency::{ExistingSource, Submission};
+use crate::schema::{FieldIssue, FieldValue, FormAnalysis, RequestType, RequestTypeField};
+use crate::snapshot::{FieldRef, SchemaDiff};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// How command results are written to stdout. Logs always go to stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// A single pretty-printed JSON document
+    Json,
+    /// Newline-delimited JSON, one compact record per line
+    Ndjson,
+}
+
+/// A command result that can be rendered in every output format
+pub trait CommandOutput: Serialize {
+    /// Human-readable rendering used for `--output text`
+    fn render_text(&self) -> String;
+
+    /// Records written one per line for `--output ndjson`
+    fn records(&self) -> Result<Vec<Value>> {
+        Ok(vec![serde_json::to_value(self)?])
+    }
+}
+
+/// Render a command result in the requested format
+pub fn render<T: CommandOutput>(format: OutputFormat, output: &T) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Text => output.render_text(),
+        OutputFormat::Json => serde_json::to_string_pretty(output)?,
+        OutputFormat::Ndjson => output
+            .records()?
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<serde_json::Result<Vec<_>>>()?
+            .join("\n"),
+    })
+}
+
+/// Result of `init`
+#[derive(Debug, Serialize)]
+pub struct InitOutput {
+    pub config_path: PathBuf,
+    /// Ticket skeleton written by the interactive wizard
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ticket_path: Option<PathBuf>,
+}
+
+impl CommandOutput for InitOutput {
+    fn render_text(&self) -> String {
+        match &self.ticket_path {
+            Some(ticket_path) => format!(
+                "Configuration file created at: {}\nTicket skeleton created at: {}\nFill in the ticket and submit it with: jsm_form submit -t {}",
+                self.config_path.display(),
+                ticket_path.display(),
+                ticket_path.display()
+            ),
+            None => format!(
+                "Configuration file created at: {}\nPlease edit the file with your credentials and settings.",
+                self.config_path.display()
+            ),
+        }
+    }
+}
+
+/// Result of `submit`
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SubmitOutput {
+    /// The request was created
+    Created { request: CreatedRequest },
+    /// The ticket was submitted before; nothing was created
+    Existing {
+        request: CreatedRequest,
+        source: ExistingSource,
+    },
+    /// `--dry-run`: the payload that would have been sent
+    DryRun {
+        payload: CreateRequestPayload,
+        attachments: Vec<PathBuf>,
+    },
+}
+
+impl CommandOutput for SubmitOutput {
+    fn render_text(&self) -> String {
+        match self {
+            SubmitOutput::Created { request } => request.to_string(),
+            SubmitOutput::Existing { request, source } => {
+                format!("{}\n{}", request, already_submitted(*source))
+            }
+            SubmitOutput::DryRun { payload, .. } => {
+                serde_json::to_string_pretty(payload).unwrap_or_default()
+            }
+        }
+    }
+}
+
+impl From<Submission> for SubmitOutput {
+    fn from(submission: Submission) -> Self {
+        match submission {
+            Submission::Created(request) => SubmitOutput::Created { request },
+            Submission::Existing { request, source } => SubmitOutput::Existing { request, source },
+        }
+    }
+}
+
+fn already_submitted(source: ExistingSource) -> &'static str {
+    match source {
+        ExistingSource::LocalState => "Already submitted (found in the local state file)",
+        ExistingSource::RemoteSearch => "Already submitted (found by searching Jira)",
+    }
+}
+
+/// Result of `attach`
+#[derive(Debug, Serialize)]
+pub struct AttachOutput {
+    pub ticket_id: String,
+    pub attached: Vec<PathBuf>,
+}
+
+impl CommandOutput for AttachOutput {
+    fn render_text(&self) -> String {
+        format!(
+            "Attached {} file(s) to {}",
+            self.attached.len(),
+            self.ticket_id
+        )
+    }
+}
+
+/// Result of `risk-assessment`
+#[derive(Debug, Serialize)]
+pub struct RiskAssessmentOutput {
+    pub ticket_id: String,
+    pub completed: bool,
+}
+
+impl CommandOutput for RiskAssessmentOutput {
+    fn render_text(&self) -> String {
+        format!("Risk assessment completed for {}", self.ticket_id)
+    }
+}
+
+/// Outcome of the risk assessment phase of `change`
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RiskAssessmentPhase {
+    Completed,
+    /// `--skip-risk-assessment`
+    Skipped,
+    /// An earlier `change` run already completed it for the existing request
+    AlreadyCompleted,
+    Failed {
+        error: String,
+    },
+}
+
+/// Result of `change`: the created request and what happened to its risk assessment
+#[derive(Debug, Serialize)]
+pub struct ChangeOutput {
+    pub request: CreatedRequest,
+    /// Set when the ticket was submitted before and the existing request was reused
+    pub existing: Option<ExistingSource>,
+    pub risk_assessment: RiskAssessmentPhase,
+}
+
+impl ChangeOutput {
+    /// True when every phase that ran succeeded
+    pub fn succeeded(&self) -> bool {
+        !matches!(self.risk_assessment, RiskAssessmentPhase::Failed { .. })
+    }
+}
+
+impl CommandOutput for ChangeOutput {
+    fn render_text(&self) -> String {
+        let risk_assessment = match &self.risk_assessment {
+            RiskAssessmentPhase::Completed => "completed".to_string(),
+            RiskAssessmentPhase::Skipped => "skipped".to_string(),
+            RiskAssessmentPhase::AlreadyCompleted => {
+                "already completed (redo it with --redo-risk-assessment)".to_string()
+            }
+            RiskAssessmentPhase::Failed { error } => format!(
+                "FAILED: {}\nRetry with: risk-assessment -i {}",
+                error, self.request.issue_key
+            ),
+        };
+        match self.existing {
+            Some(source) => format!(
+                "{}\n{}\nRisk assessment: {}",
+                self.request,
+                already_submitted(source),
+                risk_assessment
+            ),
+            None => format!("{}\nRisk assessment: {}", self.request, risk_assessment),
+        }
+    }
+}
+
+/// Result of `analyze`
+#[derive(Debug, Serialize)]
+pub struct AnalyzeOutput {
+    #[serde(flatten)]
+    pub analysis: FormAnalysis,
+    /// Valid values listed per field in the text table
+    #[serde(skip)]
+    pub max_values: usize,
+}
+
+impl CommandOutput for AnalyzeOutput {
+    fn render_text(&self) -> String {
+        let request_type = &self.analysis.request_type;
+        let mut out = format!(
+            "Request type: {} ({})\n",
+            request_type.name, request_type.id
+        );
+        if !request_type.description.is_empty() {
+            out.push_str(&format!("Description: {}\n", request_type.description));
+        }
+        if !request_type.help_text.is_empty() {
+            out.push_str(&format!("Help text: {}\n", request_type.help_text));
+        }
+
+        let rows: Vec<Vec<String>> = self
+            .analysis
+            .fields
+            .request_type_fields
+            .iter()
+            .map(|field| {
+                let mut name = field.name.clone();
+                if !field.visible {
+                    name.push_str(" (hidden)");
+                }
+                vec![
+                    field.field_id.clone(),
+                    name,
+                    if field.required { "yes" } else { "no" }.to_string(),
+                    field.type_name(),
+                    summarize_values(&field.valid_values, self.max_values),
+                ]
+            })
+            .collect();
+        out.push('\n');
+        out.push_str(&crate::table::render(
+            &["Field ID", "Name", "Required", "Type", "Valid values"],
+            &rows,
+        ));
+        out
+    }
+
+    /// The request type, then one record per field
+    fn records(&self) -> Result<Vec<Value>> {
+        let mut records = vec![json!({ "request_type": self.analysis.request_type })];
+        for field in &self.analysis.fields.request_type_fields {
+            records.push(json!({ "field": field }));
+        }
+        Ok(records)
+    }
+}
+
+/// The first `max` option labels, noting how many were left out
+fn summarize_values(values: &[FieldValue], max: usize) -> String {
+    let mut listed: Vec<String> = values
+        .iter()
+        .take(max)
+        .map(|value| value.display().to_string())
+        .collect();
+    if values.len() > max {
+        listed.push(format!("... ({} more)", values.len() - max));
+    }
+    listed.join(", ")
+}
+
+/// Result of `analyze --field`
+#[derive(Debug, Serialize)]
+pub struct FieldOutput {
+    pub field: RequestTypeField,
+}
+
+impl CommandOutput for FieldOutput {
+    fn render_text(&self) -> String {
+        let field = &self.field;
+        let mut out = format!(
+            "Field: {} ({})\nRequired: {}\nVisible: {}\nType: {}\n",
+            field.name,
+            field.field_id,
+            if field.required { "yes" } else { "no" },
+            if field.visible { "yes" } else { "no" },
+            field.type_name()
+        );
+        if let Some(custom) = &field.jira_schema.custom {
+            out.push_str(&format!("Custom type: {}\n", custom));
+        }
+        if !field.description.is_empty() {
+            out.push_str(&format!("Description: {}\n", field.description));
+        }
+        if !field.default_values.is_empty() {
+            out.push_str(&format!(
+                "Default: {}\n",
+                summarize_values(&field.default_values, usize::MAX)
+            ));
+        }
+        if field.valid_values.is_empty() {
+            out.push_str("Valid values: any");
+            return out;
+        }
+
+        // Cascading selects list their child options under the parent
+        let mut rows = Vec::new();
+        for value in &field.valid_values {
+            rows.push(vec![value.value.clone(), value.display().to_string()]);
+            for child in &value.children {
+                rows.push(vec![
+                    child.value.clone(),
+                    format!("{} / {}", value.display(), child.display()),
+                ]);
+            }
+        }
+        out.push_str(&format!("Valid values ({}):\n", field.valid_values.len()));
+        out.push_str(&crate::table::render(&["Value", "Label"], &rows));
+        out
+    }
+}
+
+/// Result of `analyze --aliases`
+#[derive(Debug, Serialize)]
+pub struct AliasesOutput {
+    pub aliases: BTreeMap<String, String>,
+    /// The table rendered as an annotated TOML section
+    #[serde(skip)]
+    pub toml: String,
+}
+
+impl CommandOutput for AliasesOutput {
+    fn render_text(&self) -> String {
+        format!("# Paste into your config file\n{}", self.toml)
+    }
+}
+
+/// Result of `analyze --template`
+#[derive(Debug, Serialize)]
+pub struct TemplateOutput {
+    pub template_path: PathBuf,
+    pub request_type: String,
+    /// Number of fields in the template
+    pub fields: usize,
+}
+
+impl CommandOutput for TemplateOutput {
+    fn render_text(&self) -> String {
+        format!(
+            "Ticket template for \"{}\" with {} fields written to {}",
+            self.request_type,
+            self.fields,
+            self.template_path.display()
+        )
+    }
+}
+
+/// Result of `schema diff`
+#[derive(Debug, Serialize)]
+pub struct SchemaDiffOutput {
+    pub baseline_path: PathBuf,
+    /// When the compared baseline was fetched; `None` when there was none yet
+    pub baseline_fetched_at: Option<String>,
+    pub diff: SchemaDiff,
+    pub breaking: bool,
+    /// Whether the live schema was saved as the new baseline
+    pub updated: bool,
+}
+
+impl CommandOutput for SchemaDiffOutput {
+    fn render_text(&self) -> String {
+        let Some(fetched_at) = &self.baseline_fetched_at else {
+            return format!(
+                "No schema baseline yet; saved the live schema to {}",
+                self.baseline_path.display()
+            );
+        };
+        if self.diff.is_empty() {
+            return format!("No changes since the baseline from {}", fetched_at);
+        }
+
+        let field_row = |change: &str, field: &FieldRef, details: String| {
+            vec![
+                change.to_string(),
+                field.field_id.clone(),
+                field.name.clone(),
+                details,
+            ]
+        };
+        let required = |field: &FieldRef| {
+            if field.required {
+                "required"
+            } else {
+                "optional"
+            }
+            .to_string()
+        };
+        let diff = &self.diff;
+        let mut rows = Vec::new();
+        for field in &diff.added {
+            rows.push(field_row("added", field, required(field)));
+        }
+        for field in &diff.removed {
+            rows.push(field_row("removed", field, String::new()));
+        }
+        for field in &diff.newly_required {
+            rows.push(field_row("now required", field, String::new()));
+        }
+        for field in &diff.no_longer_required {
+            rows.push(field_row("now optional", field, String::new()));
+        }
+        for change in &diff.type_changed {
+            rows.push(field_row(
+                "type changed",
+                &change.field,
+                format!("{} -> {}", change.before, change.after),
+            ));
+        }
+        for change in &diff.options_changed {
+            let mut details: Vec<String> = change
+                .added
+                .iter()
+                .map(|value| format!("+{}", value))
+                .collect();
+            details.extend(change.removed.iter().map(|value| format!("-{}", value)));
+            rows.push(field_row(
+                "options changed",
+                &change.field,
+                details.join(", "),
+            ));
+        }
+
+        format!(
+            "Changes since the baseline from {}:\n{}\n{}",
+            fetched_at,
+            crate::table::render(&["Change", "Field ID", "Name", "Details"], &rows),
+            if self.breaking {
+                "Breaking changes found: existing ticket files may fail to submit."
+            } else {
+                "No breaking changes."
+            }
+        )
+    }
+}
+
+/// Result of `validate`
+#[derive(Debug, Serialize)]
+pub struct ValidateOutput {
+    pub valid: bool,
+    /// Whether the fields were checked against a field schema, not just local rules
+    pub schema_checked: bool,
+    pub issues: Vec<FieldIssue>,
+}
+
+impl CommandOutput for ValidateOutput {
+    fn render_text(&self) -> String {
+        let scope = if self.schema_checked {
+            "field schema and local rules"
+        } else {
+            "local rules only"
+        };
+        if self.valid {
+            return format!("Ticket is valid ({})", scope);
+        }
+        let rows: Vec<Vec<String>> = self
+            .issues
+            .iter()
+            .map(|issue| {
+                vec![
+                    issue.field_id.clone(),
+                    issue.field_name.clone().unwrap_or_else(|| "-".to_string()),
+                    issue.message.clone(),
+                ]
+            })
+            .collect();
+        format!(
+            "Ticket has {} problem(s) ({}):\n{}",
+            self.issues.len(),
+            scope,
+            crate::table::render(&["Field ID", "Field name", "Message"], &rows)
+        )
+    }
+
+    /// One record per issue, or a single status record for a valid ticket
+    fn records(&self) -> Result<Vec<Value>> {
+        if self.issues.is_empty() {
+            return Ok(vec![json!({
+                "valid": self.valid,
+                "schema_checked": self.schema_checked,
+            })]);
+        }
+        Ok(self
+            .issues
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<_>>()?)
+    }
+}
+
+/// A failed command, written to stdout instead of a result with `--output json|ndjson`
+#[derive(Debug, Serialize)]
+pub struct ErrorOutput {
+    /// The error and its causes on one line
+    pub error: String,
+    /// The exit code of the process, see the README
+    pub exit_code: u8,
+    /// Field problems of a ticket that failed validation or was rejected by JSM
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<FieldIssue>,
+}
+
+impl ErrorOutput {
+    pub fn new(error: &anyhow::Error, exit_code: u8) -> Self {
+        let issues = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<crate::JsmError>())
+            .map(|error| error.field_issues().to_vec())
+            .unwrap_or_default();
+        Self {
+            error: format!("{:#}", error),
+            exit_code,
+            issues,
+        }
+    }
+}
+
+impl CommandOutput for ErrorOutput {
+    fn render_text(&self) -> String {
+        format!("Error: {}", self.error)
+    }
+}
+
+/// A config file layer as listed by `config show`
+#[derive(Debug, Serialize)]
+pub struct ConfigFile {
+    pub layer: String,
+    pub path: PathBuf,
+    pub exists: bool,
+}
+
+/// Result of `config show`
+#[derive(Debug, Serialize)]
+pub struct ConfigShowOutput {
+    /// Config files in order of priority, lowest first
+    pub files: Vec<ConfigFile>,
+    /// Effective values with `--resolved`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<ResolvedValue>>,
+}
+
+impl CommandOutput for ConfigShowOutput {
+    fn render_text(&self) -> String {
+        let Some(values) = &self.values else {
+            let rows: Vec<Vec<String>> = self
+                .files
+                .iter()
+                .map(|file| {
+                    vec![
+                        file.layer.clone(),
+                        file.path.display().to_string(),
+                        if file.exists { "found" } else { "missing" }.to_string(),
+                    ]
+                })
+                .collect();
+            return format!(
+                "Config files, lowest priority first (then {}_* variables and --set):\n{}",
+                crate::config::ENV_PREFIX,
+                crate::table::render(&["Layer", "Path", "Status"], &rows)
+            );
+        };
+        let rows: Vec<Vec<String>> = values
+            .iter()
+            .map(|value| vec![value.key.clone(), value.value.clone(), value.source.clone()])
+            .collect();
+        crate::table::render(&["Key", "Value", "Source"], &rows)
+    }
+}
+
+/// A profile as listed by `profiles list`, with the values it resolves to
+#[derive(Debug, Serialize)]
+pub struct ProfileSummary {
+    pub name: String,
+    /// Selected with `--profile`, `JSM_PROFILE` or `profile` in a config file
+    pub active: bool,
+    pub base_url: String,
+    /// Service desk name, or the portal ID
+    pub service_desk: String,
+    /// Request type name, or its ID
+    pub request_type: String,
+    pub username: String,
+}
+
+/// Result of `profiles list`
+#[derive(Debug, Serialize)]
+pub struct ProfilesOutput {
+    pub profiles: Vec<ProfileSummary>,
+}
+
+impl CommandOutput for ProfilesOutput {
+    fn render_text(&self) -> String {
+        if self.profiles.is_empty() {
+            return "No profiles defined; add [profiles.<name>] sections to the config".to_string();
+        }
+        let rows: Vec<Vec<String>> = self
+            .profiles
+            .iter()
+            .map(|profile| {
+                vec![
+                    if profile.active { "*" } else { "" }.to_string(),
+                    profile.name.clone(),
+                    profile.base_url.clone(),
+                    profile.service_desk.clone(),
+                    profile.request_type.clone(),
+                    profile.username.clone(),
+                ]
+            })
+            .collect();
+        crate::table::render(
+            &[
+                "",
+                "Profile",
+                "Site",
+                "Service desk",
+                "Request type",
+                "User",
+            ],
+            &rows,
+        )
+    }
+
+    /// One record per profile
+    fn records(&self) -> Result<Vec<Value>> {
+        Ok(self
+            .profiles
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<_>>()?)
+    }
+}
+
+/// Result of `list service-desks`
+#[derive(Debug, Serialize)]
+pub struct ServiceDesksOutput {
+    pub service_desks: Vec<ServiceDesk>,
+}
+
+impl CommandOutput for ServiceDesksOutput {
+    fn render_text(&self) -> String {
+        let rows: Vec<Vec<String>> = self
+            .service_desks
+            .iter()
+            .map(|desk| {
+                vec![
+                    desk.id.clone(),
+                    desk.project_key.clone(),
+                    desk.project_name.clone(),
+                ]
+            })
+            .collect();
+        crate::table::render(&["ID", "Key", "Name"], &rows)
+    }
+
+    /// One record per service desk
+    fn records(&self) -> Result<Vec<Value>> {
+        Ok(self
+            .service_desks
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<_>>()?)
+    }
+}
+
+/// Result of `list request-types`
+#[derive(Debug, Serialize)]
+pub struct RequestTypesOutput {
+    pub service_desk_id: String,
+    pub request_types: Vec<RequestType>,
+}
+
+impl CommandOutput for RequestTypesOutput {
+    fn render_text(&self) -> String {
+        let rows: Vec<Vec<String>> = self
+            .request_types
+            .iter()
+            .map(|request_type| {
+                vec![
+                    request_type.id.clone(),
+                    request_type.name.clone(),
+                    request_type.description.clone(),
+                ]
+            })
+            .collect();
+        format!(
+            "Request types of service desk {}:\n{}",
+            self.service_desk_id,
+            crate::table::render(&["ID", "Name", "Description"], &rows)
+        )
+    }
+
+    /// One record per request type
+    fn records(&self) -> Result<Vec<Value>> {
+        Ok(self
+            .request_types
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<_>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    fn created_request() -> CreatedRequest {
+        CreatedRequest {
+            issue_key: "ITH-1".to_string(),
+            issue_id: "1001".to_string(),
+            request_type_id: "73".to_string(),
+            service_desk_id: "6".to_string(),
+            browse_url: "https://example.atlassian.net/browse/ITH-1".to_string(),
+            portal_url: "https://example.atlassian.net/servicedesk/customer/portal/6/ITH-1"
+                .to_string(),
+            raw: json!({ "issueKey": "ITH-1" }),
+        }
+    }
+
+    #[test]
+    fn submit_json_schema_is_stable() {
+        let created = serde_json::to_value(SubmitOutput::Created {
+            request: created_request(),
+        })
+        .unwrap();
+        assert_eq!(keys(&created), vec!["request", "status"]);
+        assert_eq!(created["status"], "created");
+        assert_eq!(
+            keys(&created["request"]),
+            vec![
+                "browse_url",
+                "issue_id",
+                "issue_key",
+                "portal_url",
+                "raw",
+                "request_type_id",
+                "service_desk_id"
+            ]
+        );
+
+        let dry_run = serde_json::to_value(SubmitOutput::DryRun {
+            payload: CreateRequestPayload {
+                service_desk_id: 6,
+                request_type_id: 73,
+                request_field_values: BTreeMap::from([("summary".to_string(), json!("x"))]),
+                raise_on_behalf_of: None,
+                request_participants: Vec::new(),
+            },
+            attachments: vec![PathBuf::from("runbook.pdf")],
+        })
+        .unwrap();
+        assert_eq!(keys(&dry_run), vec!["attachments", "payload", "status"]);
+        assert_eq!(dry_run["status"], "dry_run");
+        assert_eq!(dry_run["payload"]["requestFieldValues"]["summary"], "x");
+    }
+
+    #[test]
+    fn command_json_schemas_are_stable() {
+        let attach = serde_json::to_value(AttachOutput {
+            ticket_id: "ITH-1".to_string(),
+            attached: vec![PathBuf::from("a.pdf")],
+        })
+        .unwrap();
+        assert_eq!(keys(&attach), vec!["attached", "ticket_id"]);
+
+        let risk = serde_json::to_value(RiskAssessmentOutput {
+            ticket_id: "ITH-1".to_string(),
+            completed: true,
+        })
+        .unwrap();
+        assert_eq!(keys(&risk), vec!["completed", "ticket_id"]);
+
+        let aliases = serde_json::to_value(AliasesOutput {
+            aliases: BTreeMap::from([("planned_start".to_string(), "customfield_1".to_string())]),
+            toml: String::new(),
+        })
+        .unwrap();
+        assert_eq!(
+            aliases,
+            json!({ "aliases": { "planned_start": "customfield_1" } })
+        );
+    }
+
+    #[test]
+    fn change_keeps_request_when_risk_assessment_fails() {
+        let output = ChangeOutput {
+            request: created_request(),
+            existing: None,
+            risk_assessment: RiskAssessmentPhase::Failed {
+                error: "browser crashed".to_string(),
+            },
+        };
+        assert!(!output.succeeded());
+
+        let value = serde_json::to_value(&output).unwrap();
+        assert_eq!(value["request"]["issue_key"], "ITH-1");
+        assert_eq!(
+            value["risk_assessment"],
+            json!({ "status": "failed", "error": "browser crashed" })
+        );
+        assert!(output.render_text().starts_with("Request ID: ITH-1\n"));
+    }
+
+    fn analyze_output() -> AnalyzeOutput {
+        AnalyzeOutput {
+            analysis: FormAnalysis {
+                request_type: RequestType {
+                    id: "73".to_string(),
+                    name: "Normal Change".to_string(),
+                    ..Default::default()
+                },
+                fields: serde_json::from_value(json!({
+                    "requestTypeFields": [
+                        { "fieldId": "summary", "name": "Summary", "required": true },
+                        { "fieldId": "description", "name": "Description" }
+                    ]
+                }))
+                .unwrap(),
+            },
+            max_values: 5,
+        }
+    }
+
+    #[test]
+    fn ndjson_emits_one_record_per_line() {
+        let analyze = analyze_output();
+
+        let rendered = render(OutputFormat::Ndjson, &analyze).unwrap();
+        let lines: Vec<Value> = rendered
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["request_type"]["name"], "Normal Change");
+        assert_eq!(lines[1]["field"]["fieldId"], "summary");
+        assert_eq!(lines[2]["field"]["fieldId"], "description");
+    }
+
+    #[test]
+    fn analyze_renders_a_field_table() {
+        let mut analyze = analyze_output();
+        analyze.analysis.fields.request_type_fields[1].valid_values =
+            serde_json::from_value(json!([
+                { "value": "1", "label": "Low" },
+                { "value": "2", "label": "Medium" },
+                { "value": "3", "label": "High" }
+            ]))
+            .unwrap();
+        analyze.max_values = 2;
+
+        let text = analyze.render_text();
+        assert!(text.starts_with("Request type: Normal Change (73)\n"));
+        assert!(text.contains("summary      Summary      yes"), "{}", text);
+        assert!(text.contains("Low, Medium, ... (1 more)"), "{}", text);
+        assert_eq!(
+            keys(&serde_json::to_value(&analyze).unwrap()),
+            vec!["fields", "request_type"]
+        );
+    }
+
+    #[test]
+    fn valid_tickets_and_errors_render_one_ndjson_record() {
+        let valid = ValidateOutput {
+            valid: true,
+            schema_checked: false,
+            issues: Vec::new(),
+        };
+        assert_eq!(
+            render(OutputFormat::Ndjson, &valid).unwrap(),
+            r#"{"schema_checked":false,"valid":true}"#
+        );
+
+        let error = anyhow::Error::from(crate::JsmError::Validation {
+            request_type_id: 73,
+            issues: vec![FieldIssue {
+                field_id: "summary".to_string(),
+                field_name: Some("Summary".to_string()),
+                kind: crate::schema::IssueKind::MissingRequired,
+                message: "is required".to_string(),
+            }],
+        })
+        .context("Submit failed");
+        let output = ErrorOutput::new(&error, 6);
+        let record: Value =
+            serde_json::from_str(&render(OutputFormat::Ndjson, &output).unwrap()).unwrap();
+        assert_eq!(record["exit_code"], 6);
+        assert_eq!(record["issues"][0]["field_id"], "summary");
+        assert!(
+            record["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("Submit failed: Ticket fields failed validation")
+        );
+    }
+
+    #[test]
+    fn lists_render_as_tables_and_one_record_per_item() {
+        let desks = ServiceDesksOutput {
+            service_desks: serde_json::from_value(json!([
+                { "id": "6", "projectId": "10010", "projectName": "IT Help", "projectKey": "ITH" },
+                { "id": "7", "projectId": "10011", "projectName": "HR", "projectKey": "HR" }
+            ]))
+            .unwrap(),
+        };
+        assert_eq!(
+            desks.render_text(),
+            "ID  Key  Name\n--  ---  -------\n6   ITH  IT Help\n7   HR   HR"
+        );
+        let rendered = render(OutputFormat::Ndjson, &desks).unwrap();
+        let lines: Vec<Value> = rendered
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["projectKey"], "HR");
+    }
+}
@@ -0,0 +1,489 @@
+use crate::JsmConfig;
+use crate::error::JsmError;
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A request type as returned by `/rest/servicedeskapi/servicedesk/{id}/requesttype/{id}`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RequestType {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(rename = "helpText", default)]
+    pub help_text: String,
+    #[serde(rename = "issueTypeId", default)]
+    pub issue_type_id: String,
+    #[serde(rename = "serviceDeskId", default)]
+    pub service_desk_id: String,
+    #[serde(rename = "portalId", default)]
+    pub portal_id: String,
+    #[serde(rename = "groupIds", default)]
+    pub group_ids: Vec<String>,
+}
+
+/// Field list of a request type as returned by
+/// `/rest/servicedeskapi/servicedesk/{id}/requesttype/{id}/field`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RequestTypeFields {
+    #[serde(rename = "requestTypeFields", default)]
+    pub request_type_fields: Vec<RequestTypeField>,
+    #[serde(rename = "canRaiseOnBehalfOf", default)]
+    pub can_raise_on_behalf_of: bool,
+    #[serde(rename = "canAddRequestParticipants", default)]
+    pub can_add_request_participants: bool,
+}
+
+impl RequestTypeFields {
+    /// Look up a field by its ID (e.g. `summary` or `customfield_10878`)
+    pub fn field(&self, field_id: &str) -> Option<&RequestTypeField> {
+        self.request_type_fields
+            .iter()
+            .find(|field| field.field_id == field_id)
+    }
+}
+
+/// A single field of a request type
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RequestTypeField {
+    #[serde(rename = "fieldId")]
+    pub field_id: String,
+    /// Display name shown on the portal form
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(rename = "validValues", default)]
+    pub valid_values: Vec<FieldValue>,
+    #[serde(rename = "defaultValues", default)]
+    pub default_values: Vec<FieldValue>,
+    #[serde(rename = "jiraSchema", default)]
+    pub jira_schema: JiraSchema,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+/// An allowed (or default) value of a select-style field
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FieldValue {
+    pub value: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub children: Vec<FieldValue>,
+}
+
+/// Jira schema describing the value type of a field
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct JiraSchema {
+    #[serde(rename = "type", default)]
+    pub field_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom: Option<String>,
+    #[serde(rename = "customId", skip_serializing_if = "Option::is_none")]
+    pub custom_id: Option<i64>,
+}
+
+impl RequestTypeField {
+    /// Readable Jira schema type, e.g. `string` or `array of option`
+    pub fn type_name(&self) -> String {
+        match (
+            self.jira_schema.field_type.as_str(),
+            self.jira_schema.items.as_deref(),
+        ) {
+            ("", _) => "unknown type".to_string(),
+            ("array", Some(items)) => format!("array of {}", items),
+            (field_type, _) => field_type.to_string(),
+        }
+    }
+}
+
+impl FieldValue {
+    /// The label shown on the portal, or the value when there is no label
+    pub fn display(&self) -> &str {
+        if self.label.is_empty() {
+            &self.value
+        } else {
+            &self.label
+        }
+    }
+}
+
+/// Structure of a request type form: its details and its fields
+#[derive(Debug, Clone, Serialize)]
+pub struct FormAnalysis {
+    pub request_type: RequestType,
+    pub fields: RequestTypeFields,
+}
+
+impl FormAnalysis {
+    /// Find a field by ID or display name (case-insensitive)
+    pub fn find_field(&self, wanted: &str) -> Option<&RequestTypeField> {
+        let wanted = wanted.trim();
+        self.fields.field(wanted).or_else(|| {
+            self.fields
+                .request_type_fields
+                .iter()
+                .find(|field| field.name.eq_ignore_ascii_case(wanted))
+        })
+    }
+}
+
+/// What kind of problem a [`FieldIssue`] is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The request type has no field with this ID
+    UnknownField,
+    /// A required field has no value
+    MissingRequired,
+    /// A value matches none of the field's options
+    UnknownOption,
+    /// The request type does not allow a setting, e.g. raising on behalf of others
+    NotAllowed,
+    /// Anything else, e.g. a value of the wrong type or an error returned by JSM
+    #[default]
+    Other,
+}
+
+/// A problem found while validating ticket fields against the request type schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldIssue {
+    pub field_id: String,
+    /// Display name from the field list, `None` for fields the request type does not know
+    pub field_name: Option<String>,
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+impl FieldIssue {
+    /// True for problems a change to the request type's field list can explain: unknown
+    /// fields, missing required fields, unknown options and settings it does not allow
+    pub fn depends_on_field_list(&self) -> bool {
+        matches!(
+            self.kind,
+            IssueKind::UnknownField
+                | IssueKind::MissingRequired
+                | IssueKind::UnknownOption
+                | IssueKind::NotAllowed
+        )
+    }
+}
+
+impl fmt::Display for FieldIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field_name {
+            Some(name) => write!(f, "{} ({}): {}", name, self.field_id, self.message),
+            None => write!(f, "{}: {}", self.field_id, self.message),
+        }
+    }
+}
+
+/// Fetch the details of the configured request type
+pub async fn fetch_request_type(client: &Client, config: &JsmConfig) -> Result<RequestType> {
+    crate::discovery::require_target(config)?;
+    let request_type_url = format!(
+        "{}/rest/servicedeskapi/servicedesk/{}/requesttype/{}",
+        config.base_url, config.portal_id, config.request_type_id
+    );
+
+    crate::log_debug!("Fetching request type details from: {}", request_type_url);
+
+    let response = crate::http::send(
+        &config.http,
+        client
+            .get(&request_type_url)
+            .basic_auth(
+                &config.auth.username,
+                Some(&config.auth.token_atlassian_api),
+            )
+            .header("Accept", "application/json"),
+    )
+    .await
+    .context("Failed to fetch request type details")?;
+
+    if response.status().is_success() {
+        response
+            .json()
+            .await
+            .context("Failed to parse request type details")
+    } else {
+        Err(JsmError::from_response(response).await.into())
+    }
+}
+
+/// Fetch the field list of the configured request type
+pub async fn fetch_request_type_fields(
+    client: &Client,
+    config: &JsmConfig,
+) -> Result<RequestTypeFields> {
+    crate::discovery::require_target(config)?;
+    let fields_url = format!(
+        "{}/rest/servicedeskapi/servicedesk/{}/requesttype/{}/field",
+        config.base_url, config.portal_id, config.request_type_id
+    );
+
+    crate::log_debug!("Fetching request type fields from: {}", fields_url);
+
+    let response = crate::http::send(
+        &config.http,
+        client
+            .get(&fields_url)
+            .basic_auth(
+                &config.auth.username,
+                Some(&config.auth.token_atlassian_api),
+            )
+            .header("Accept", "application/json"),
+    )
+    .await
+    .context("Failed to fetch request type fields")?;
+
+    if response.status().is_success() {
+        response
+            .json()
+            .await
+            .context("Failed to parse request type fields")
+    } else {
+        Err(JsmError::from_response(response).await.into())
+    }
+}
+
+/// Validate ticket fields against the request type schema.
+///
+/// Reports missing required fields, keys the request type does not know and
+/// values whose JSON shape does not match the field's Jira schema type.
+pub fn validate_fields(
+    fields: &BTreeMap<String, Value>,
+    schema: &RequestTypeFields,
+) -> Vec<FieldIssue> {
+    let mut issues = Vec::new();
+
+    for field in &schema.request_type_fields {
+        if field.required && fields.get(&field.field_id).is_none_or(is_empty_value) {
+            issues.push(FieldIssue {
+                field_id: field.field_id.clone(),
+                field_name: Some(field.name.clone()),
+                kind: IssueKind::MissingRequired,
+                message: "is required".to_string(),
+            });
+        }
+    }
+
+    for (key, value) in fields {
+        match schema.field(key) {
+            None => issues.push(FieldIssue {
+                field_id: key.clone(),
+                field_name: None,
+                kind: IssueKind::UnknownField,
+                message: "is not a field of this request type".to_string(),
+            }),
+            Some(field) => {
+                if is_empty_value(value) {
+                    continue;
+                }
+                if let Some(message) = check_value_shape(&field.jira_schema, value) {
+                    issues.push(FieldIssue {
+                        field_id: key.clone(),
+                        field_name: Some(field.name.clone()),
+                        kind: IssueKind::Other,
+                        message,
+                    });
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+fn is_empty_value(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.trim().is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+/// Check that a value has the JSON shape expected for a Jira schema type
+fn check_value_shape(schema: &JiraSchema, value: &Value) -> Option<String> {
+    let expected = match schema.field_type.as_str() {
+        "string" | "date" | "datetime" => matches!(value, Value::String(_))
+            .then_some(())
+            .ok_or("a string"),
+        "number" => matches!(value, Value::Number(_))
+            .then_some(())
+            .ok_or("a number"),
+        "option" | "priority" | "user" => is_option_like(value)
+            .then_some(())
+            .ok_or("a string or an object with an id/value"),
+        "array" => match value {
+            Value::Array(items) => match schema.items.as_deref() {
+                Some("string") => items
+                    .iter()
+                    .all(Value::is_string)
+                    .then_some(())
+                    .ok_or("an array of strings"),
+                Some("option") | Some("user") => items
+                    .iter()
+                    .all(is_option_like)
+                    .then_some(())
+                    .ok_or("an array of strings or objects with an id/value"),
+                _ => Ok(()),
+            },
+            _ => Err("an array"),
+        },
+        _ => Ok(()),
+    };
+
+    expected.err().map(|expected| {
+        format!(
+            "expected {} for schema type '{}', got {}",
+            expected,
+            schema.field_type,
+            json_type_name(value)
+        )
+    })
+}
+
+fn is_option_like(value: &Value) -> bool {
+    match value {
+        Value::String(_) => true,
+        Value::Object(map) => {
+            map.contains_key("id")
+                || map.contains_key("value")
+                || map.contains_key("name")
+                || map.contains_key("accountId")
+        }
+        _ => false,
+    }
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_schema() -> RequestTypeFields {
+        serde_json::from_value(json!({
+            "requestTypeFields": [
+                {
+                    "fieldId": "summary",
+                    "name": "Summary",
+                    "required": true,
+                    "jiraSchema": { "type": "string", "system": "summary" }
+                },
+                {
+                    "fieldId": "customfield_10878",
+                    "name": "Planned start",
+                    "required": true,
+                    "jiraSchema": { "type": "datetime", "custom": "com.atlassian.jira.plugin.system.customfieldtypes:datetime", "customId": 10878 }
+                },
+                {
+                    "fieldId": "customfield_10900",
+                    "name": "Impact",
+                    "required": false,
+                    "validValues": [ { "value": "10001", "label": "Low", "children": [] } ],
+                    "jiraSchema": { "type": "option" }
+                }
+            ],
+            "canRaiseOnBehalfOf": true,
+            "canAddRequestParticipants": true
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn valid_fields_produce_no_issues() {
+        let fields = BTreeMap::from([
+            ("summary".to_string(), json!("Deploy")),
+            (
+                "customfield_10878".to_string(),
+                json!("2025-09-23T14:00:00.000+1300"),
+            ),
+            ("customfield_10900".to_string(), json!({ "id": "10001" })),
+        ]);
+        assert!(validate_fields(&fields, &sample_schema()).is_empty());
+    }
+
+    #[test]
+    fn reports_missing_unknown_and_mistyped_fields() {
+        let fields = BTreeMap::from([
+            ("summary".to_string(), json!("   ")),
+            ("customfield_10900".to_string(), json!(42)),
+            ("customfield_99999".to_string(), json!("typo")),
+        ]);
+        let issues = validate_fields(&fields, &sample_schema());
+        let rendered: Vec<String> = issues.iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            rendered,
+            vec![
+                "Summary (summary): is required",
+                "Planned start (customfield_10878): is required",
+                "Impact (customfield_10900): expected a string or an object with an id/value for schema type 'option', got a number",
+                "customfield_99999: is not a field of this request type",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_schema_fetches_are_typed_errors() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/rest/servicedeskapi/servicedesk/6/requesttype/73/field",
+            ))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/rest/servicedeskapi/servicedesk/6/requesttype/73"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .set_body_json(json!({ "errorMessage": "No permission" })),
+            )
+            .mount(&server)
+            .await;
+
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        let client = Client::new();
+        let error = fetch_request_type_fields(&client, &config)
+            .await
+            .unwrap_err();
+        assert_eq!(error.downcast_ref::<JsmError>().unwrap().exit_code(), 4);
+        let error = fetch_request_type(&client, &config).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<JsmError>(),
+            Some(JsmError::Forbidden { message, .. }) if message.contains("No permission")
+        ));
+    }
+}
@@ -0,0 +1,545 @@
+use crate::JsmConfig;
+use crate::error::JsmError;
+use crate::schema::{FieldIssue, IssueKind, RequestTypeField, RequestTypeFields};
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Jira custom field type of the "Affected services" field
+pub const SERVICE_ENTITY_FIELD_TYPE: &str =
+    "com.atlassian.jira.plugins.service-entity:service-entity-field-cftype";
+
+/// Default location of the local service registry cache
+pub const DEFAULT_SERVICES_CACHE: &str = "jsm_services_cache.pvt.json";
+
+/// Services fetched per GraphQL page
+const PAGE_SIZE: u32 = 100;
+
+const SERVICES_QUERY: &str = r#"query JsmFormServices($cloudId: String!, $first: Int, $after: String) {
+  devOpsServices(cloudId: $cloudId, first: $first, after: $after) {
+    edges { node { id name description tier { level } } }
+    pageInfo { hasNextPage endCursor }
+  }
+}"#;
+
+/// A service from the service registry, in the shape the Affected services field expects
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ServiceEntity {
+    /// Service ARI, e.g. `ari:cloud:graph::service/<cloud id>/<service id>`
+    pub id: String,
+    pub name: String,
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tier: Option<ServiceTier>,
+}
+
+fn null_as_empty<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<String, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ServiceTier {
+    pub level: u32,
+}
+
+/// On-disk cache of the service registry for one site
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ServiceCache {
+    pub base_url: String,
+    pub services: Vec<ServiceEntity>,
+}
+
+/// Returns true if the field holds service registry entities
+pub fn is_service_field(field: &RequestTypeField) -> bool {
+    field.jira_schema.custom.as_deref() == Some(SERVICE_ENTITY_FIELD_TYPE)
+}
+
+fn cache_path(config: &JsmConfig) -> PathBuf {
+    config
+        .services_cache
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SERVICES_CACHE))
+}
+
+/// Load the service cache, ignoring caches written for a different site
+pub fn load_cache(path: &Path, base_url: &str) -> Option<ServiceCache> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let cache: ServiceCache = match serde_json::from_str(&contents) {
+        Ok(cache) => cache,
+        Err(err) => {
+            crate::log_warn!(
+                "Ignoring unreadable service cache {}: {}",
+                path.display(),
+                err
+            );
+            return None;
+        }
+    };
+    (cache.base_url == base_url).then_some(cache)
+}
+
+/// Write the service cache
+pub fn save_cache(path: &Path, cache: &ServiceCache) -> Result<()> {
+    let contents = serde_json::to_string_pretty(cache)?;
+    std::fs::write(path, contents)
+        .with_context(|| format!("Failed to write service cache: {}", path.display()))
+}
+
+/// Look up the Atlassian cloud ID of the site
+pub async fn fetch_cloud_id(client: &Client, config: &JsmConfig) -> Result<String> {
+    #[derive(Deserialize)]
+    struct TenantInfo {
+        #[serde(rename = "cloudId")]
+        cloud_id: String,
+    }
+
+    let tenant_url = format!("{}/_edge/tenant_info", config.base_url);
+    let response = crate::http::send(&config.http, client.get(&tenant_url))
+        .await
+        .context("Failed to fetch tenant info")?;
+    if !response.status().is_success() {
+        return Err(JsmError::from_response(response).await.into());
+    }
+    let info: TenantInfo = response
+        .json()
+        .await
+        .context("Failed to parse tenant info")?;
+    Ok(info.cloud_id)
+}
+
+/// Fetch every service in the service registry of the site
+pub async fn fetch_services(client: &Client, config: &JsmConfig) -> Result<Vec<ServiceEntity>> {
+    let cloud_id = fetch_cloud_id(client, config).await?;
+    let graphql_url = format!("{}/gateway/api/graphql", config.base_url);
+
+    let mut services = Vec::new();
+    let mut after: Option<String> = None;
+    loop {
+        let response = crate::http::send(
+            &config.http,
+            client
+                .post(&graphql_url)
+                .basic_auth(
+                    &config.auth.username,
+                    Some(&config.auth.token_atlassian_api),
+                )
+                .header("Accept", "application/json")
+                .json(&json!({
+                    "query": SERVICES_QUERY,
+                    "variables": { "cloudId": cloud_id, "first": PAGE_SIZE, "after": after },
+                })),
+        )
+        .await
+        .context("Failed to query the service registry")?;
+
+        if !response.status().is_success() {
+            return Err(JsmError::from_response(response).await.into());
+        }
+        let body: Value = response
+            .json()
+            .await
+            .context("Failed to parse service registry response")?;
+        // GraphQL reports query errors with a success status
+        if let Some(errors) = body.get("errors").filter(|errors| !errors.is_null()) {
+            return Err(anyhow::anyhow!("Service registry query failed: {}", errors));
+        }
+
+        let connection = &body["data"]["devOpsServices"];
+        for edge in connection["edges"].as_array().into_iter().flatten() {
+            let service: ServiceEntity = serde_json::from_value(edge["node"].clone())
+                .context("Unexpected service entry in service registry response")?;
+            services.push(service);
+        }
+
+        let page_info = &connection["pageInfo"];
+        match page_info["endCursor"].as_str() {
+            // A cursor that does not advance would fetch the same page forever
+            Some(cursor) if after.as_deref() == Some(cursor) => {
+                crate::log_warn!(
+                    "Service registry returned the same page cursor twice; stopping after {} services",
+                    services.len()
+                );
+                break;
+            }
+            Some(cursor) if page_info["hasNextPage"].as_bool() == Some(true) => {
+                after = Some(cursor.to_string());
+            }
+            _ => break,
+        }
+    }
+
+    crate::log_info!(
+        "Fetched {} services from the service registry",
+        services.len()
+    );
+    Ok(services)
+}
+
+/// Find the service with the given name (case-insensitive)
+pub fn match_service<'a>(
+    name: &str,
+    services: &'a [ServiceEntity],
+) -> std::result::Result<&'a ServiceEntity, String> {
+    let wanted = name.trim();
+    let exact: Vec<&ServiceEntity> = services
+        .iter()
+        .filter(|service| service.name.trim().eq_ignore_ascii_case(wanted))
+        .collect();
+
+    match exact.as_slice() {
+        [service] => Ok(service),
+        [] => {
+            let wanted_lower = wanted.to_lowercase();
+            let similar: Vec<&str> = services
+                .iter()
+                .filter(|service| service.name.to_lowercase().contains(&wanted_lower))
+                .map(|service| service.name.as_str())
+                .take(5)
+                .collect();
+            if similar.is_empty() {
+                Err(format!("unknown service '{}'", wanted))
+            } else {
+                Err(format!(
+                    "unknown service '{}'. Similar services: {}",
+                    wanted,
+                    similar.join(", ")
+                ))
+            }
+        }
+        several => Err(format!(
+            "service name '{}' is ambiguous, it matches {}; use the full service object with its id instead",
+            wanted,
+            several
+                .iter()
+                .map(|service| service.id.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Replace service names in Affected services fields with full service entities.
+///
+/// Names are looked up in the local cache first; the cache is refreshed from the
+/// service registry once if any name is missing.
+pub async fn resolve_service_fields(
+    client: &Client,
+    config: &JsmConfig,
+    fields: &mut BTreeMap<String, Value>,
+    schema: &RequestTypeFields,
+) -> Result<Vec<FieldIssue>> {
+    let mut pending: Vec<(String, Vec<String>)> = Vec::new();
+    for (field_id, value) in fields.iter() {
+        if !schema.field(field_id).is_some_and(is_service_field) {
+            continue;
+        }
+        let names: Vec<String> = match value {
+            Value::String(name) => vec![name.clone()],
+            Value::Array(items) => items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        if !names.is_empty() {
+            pending.push((field_id.clone(), names));
+        }
+    }
+    if pending.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let path = cache_path(config);
+    let mut cache = load_cache(&path, &config.base_url).unwrap_or_default();
+    let all_cached = pending
+        .iter()
+        .flat_map(|(_, names)| names)
+        .all(|name| match_service(name, &cache.services).is_ok());
+    if !all_cached {
+        cache = ServiceCache {
+            base_url: config.base_url.clone(),
+            services: fetch_services(client, config).await?,
+        };
+        save_cache(&path, &cache)?;
+    }
+
+    let mut issues = Vec::new();
+    for (field_id, _) in pending {
+        let field_name = schema.field(&field_id).map(|field| field.name.clone());
+        let value = fields.get_mut(&field_id).expect("pending field exists");
+        let items = match value.take() {
+            Value::Array(items) => items,
+            single => vec![single],
+        };
+
+        let mut resolved = Vec::with_capacity(items.len());
+        for item in items {
+            match item {
+                Value::String(name) => match match_service(&name, &cache.services) {
+                    Ok(service) => resolved.push(serde_json::to_value(service)?),
+                    Err(message) => {
+                        issues.push(FieldIssue {
+                            field_id: field_id.clone(),
+                            field_name: field_name.clone(),
+                            kind: IssueKind::Other,
+                            message,
+                        });
+                        resolved.push(Value::String(name));
+                    }
+                },
+                other => resolved.push(other),
+            }
+        }
+        *value = Value::Array(resolved);
+    }
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(id: &str, name: &str) -> ServiceEntity {
+        ServiceEntity {
+            id: format!("ari:cloud:graph::service/cloud/{}", id),
+            name: name.to_string(),
+            description: String::new(),
+            tier: Some(ServiceTier { level: 1 }),
+        }
+    }
+
+    #[test]
+    fn matches_service_names() {
+        let services = vec![
+            service("1", "Azure Cloud"),
+            service("2", "Customer Portal"),
+            service("3", "Customer Portal API"),
+            service("4", "Billing"),
+            service("5", "billing"),
+        ];
+
+        assert_eq!(
+            match_service("azure cloud", &services).unwrap().id,
+            services[0].id
+        );
+        assert_eq!(
+            match_service("Customer Portal", &services).unwrap().id,
+            services[1].id
+        );
+
+        let unknown = match_service("Portal", &services).unwrap_err();
+        assert_eq!(
+            unknown,
+            "unknown service 'Portal'. Similar services: Customer Portal, Customer Portal API"
+        );
+        assert!(
+            match_service("Billing", &services)
+                .unwrap_err()
+                .contains("ambiguous")
+        );
+    }
+
+    #[test]
+    fn serializes_like_the_affected_services_field() {
+        let value = serde_json::to_value(service("1", "Azure Cloud")).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "id": "ari:cloud:graph::service/cloud/1",
+                "name": "Azure Cloud",
+                "description": "",
+                "tier": { "level": 1 }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_service_lookups_are_typed_errors() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/_edge/tenant_info"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "cloudId": "c1" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/gateway/api/graphql"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        let error = fetch_services(&Client::new(), &config).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<JsmError>(),
+            Some(JsmError::Forbidden { .. })
+        ));
+    }
+
+    fn service_schema() -> RequestTypeFields {
+        serde_json::from_value(json!({
+            "requestTypeFields": [{
+                "fieldId": "customfield_10243",
+                "name": "Affected services",
+                "jiraSchema": { "type": "array", "items": "service-entity-field-value",
+                                "custom": SERVICE_ENTITY_FIELD_TYPE }
+            }]
+        }))
+        .unwrap()
+    }
+
+    /// One GraphQL page of the service registry
+    fn services_page(services: &[ServiceEntity], end_cursor: &str, has_next: bool) -> Value {
+        json!({
+            "data": { "devOpsServices": {
+                "edges": services.iter().map(|service| json!({ "node": service })).collect::<Vec<_>>(),
+                "pageInfo": { "hasNextPage": has_next, "endCursor": end_cursor }
+            } }
+        })
+    }
+
+    /// A mock site whose registry holds `services`, expecting `fetches` registry queries
+    async fn registry(
+        services: &[ServiceEntity],
+        fetches: u64,
+    ) -> (wiremock::MockServer, JsmConfig) {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/_edge/tenant_info"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "cloudId": "c1" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/gateway/api/graphql"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(services_page(services, "c-1", false)),
+            )
+            .expect(fetches)
+            .mount(&server)
+            .await;
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        config.http.max_retries = 0;
+        (server, config)
+    }
+
+    #[tokio::test]
+    async fn resolves_names_from_the_cache_without_querying_the_registry() {
+        let (_server, mut config) = registry(&[], 0).await;
+        let dir = tempfile::tempdir().unwrap();
+        let cache_file = dir.path().join("services.json");
+        config.services_cache = Some(cache_file.clone());
+        save_cache(
+            &cache_file,
+            &ServiceCache {
+                base_url: config.base_url.clone(),
+                services: vec![service("1", "Azure Cloud")],
+            },
+        )
+        .unwrap();
+
+        let mut fields = BTreeMap::from([("customfield_10243".to_string(), json!("azure cloud"))]);
+        let issues =
+            resolve_service_fields(&Client::new(), &config, &mut fields, &service_schema())
+                .await
+                .unwrap();
+        assert!(issues.is_empty(), "{issues:?}");
+        assert_eq!(
+            fields["customfield_10243"],
+            json!([serde_json::to_value(service("1", "Azure Cloud")).unwrap()])
+        );
+    }
+
+    #[tokio::test]
+    async fn refreshes_the_cache_when_a_name_is_missing() {
+        let (_server, mut config) =
+            registry(&[service("1", "Azure Cloud"), service("2", "Billing")], 1).await;
+        let dir = tempfile::tempdir().unwrap();
+        let cache_file = dir.path().join("services.json");
+        config.services_cache = Some(cache_file.clone());
+        // Written before "Billing" was added to the registry
+        save_cache(
+            &cache_file,
+            &ServiceCache {
+                base_url: config.base_url.clone(),
+                services: vec![service("1", "Azure Cloud")],
+            },
+        )
+        .unwrap();
+
+        let mut fields = BTreeMap::from([(
+            "customfield_10243".to_string(),
+            json!(["Azure Cloud", "Billing"]),
+        )]);
+        let issues =
+            resolve_service_fields(&Client::new(), &config, &mut fields, &service_schema())
+                .await
+                .unwrap();
+        assert!(issues.is_empty(), "{issues:?}");
+        assert_eq!(fields["customfield_10243"][1]["name"], "Billing");
+        let cache = load_cache(&cache_file, &config.base_url).unwrap();
+        assert_eq!(cache.services.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn reports_unknown_service_names() {
+        let (_server, mut config) = registry(&[service("1", "Azure Cloud")], 1).await;
+        let dir = tempfile::tempdir().unwrap();
+        config.services_cache = Some(dir.path().join("services.json"));
+
+        let mut fields = BTreeMap::from([(
+            "customfield_10243".to_string(),
+            json!(["Azure Cloud", "Payroll"]),
+        )]);
+        let issues =
+            resolve_service_fields(&Client::new(), &config, &mut fields, &service_schema())
+                .await
+                .unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field_name.as_deref(), Some("Affected services"));
+        assert_eq!(issues[0].message, "unknown service 'Payroll'");
+        assert_eq!(fields["customfield_10243"][1], json!("Payroll"));
+    }
+
+    #[tokio::test]
+    async fn stops_when_the_page_cursor_does_not_advance() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/_edge/tenant_info"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "cloudId": "c1" })))
+            .mount(&server)
+            .await;
+        // Always claims another page behind the same cursor
+        Mock::given(method("POST"))
+            .and(path("/gateway/api/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(services_page(
+                &[service("1", "Azure Cloud")],
+                "c-1",
+                true,
+            )))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        let services = fetch_services(&Client::new(), &config).await.unwrap();
+        assert_eq!(services.len(), 2);
+    }
+}
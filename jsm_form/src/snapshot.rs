@@ -0,0 +1,391 @@
+use crate::JsmConfig;
+use crate::schema::{RequestTypeField, RequestTypeFields};
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Settings of the on-disk request type schema cache (`[schema_cache]` section)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SchemaCacheConfig {
+    /// Directory holding one snapshot per site, service desk and request type
+    #[serde(default = "default_dir")]
+    pub dir: PathBuf,
+    /// Age after which a snapshot is fetched again; 0 disables the cache
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+fn default_dir() -> PathBuf {
+    PathBuf::from("jsm_schema_cache.pvt")
+}
+
+fn default_ttl_secs() -> u64 {
+    24 * 60 * 60
+}
+
+impl Default for SchemaCacheConfig {
+    fn default() -> Self {
+        Self {
+            dir: default_dir(),
+            ttl_secs: default_ttl_secs(),
+        }
+    }
+}
+
+impl SchemaCacheConfig {
+    /// True when no setting differs from the defaults
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Field list of a request type as fetched at a point in time
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SchemaSnapshot {
+    pub base_url: String,
+    pub portal_id: u32,
+    pub request_type_id: u32,
+    /// RFC 3339 time of the fetch
+    pub fetched_at: String,
+    pub fields: RequestTypeFields,
+}
+
+impl SchemaSnapshot {
+    pub fn new(config: &JsmConfig, fields: RequestTypeFields) -> Self {
+        Self {
+            base_url: config.base_url.clone(),
+            portal_id: config.portal_id,
+            request_type_id: config.request_type_id,
+            fetched_at: chrono::Utc::now().to_rfc3339(),
+            fields,
+        }
+    }
+
+    /// Whether the snapshot is younger than `ttl_secs`
+    pub fn is_fresh(&self, ttl_secs: u64) -> bool {
+        chrono::DateTime::parse_from_rfc3339(&self.fetched_at)
+            .map(|fetched_at| {
+                let age = chrono::Utc::now() - fetched_at.with_timezone(&chrono::Utc);
+                age.num_seconds() >= 0 && (age.num_seconds() as u64) < ttl_secs
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// Snapshot file of the configured request type:
+/// `<dir>/<host>_<portal_id>_<request_type_id>.json`
+pub fn snapshot_path(config: &JsmConfig) -> PathBuf {
+    let host = url::Url::parse(&config.base_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| config.org.clone());
+    let host: String = host
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    config.schema_cache.dir.join(format!(
+        "{}_{}_{}.json",
+        host, config.portal_id, config.request_type_id
+    ))
+}
+
+/// Drift baseline of the configured request type, next to the cached snapshot:
+/// `<dir>/<host>_<portal_id>_<request_type_id>.baseline.json`. Only `schema diff` writes it;
+/// the TTL cache never does, so form changes cannot slip into it unreported.
+pub fn baseline_path(config: &JsmConfig) -> PathBuf {
+    snapshot_path(config).with_extension("baseline.json")
+}
+
+/// Load the cached snapshot, ignoring one written for a different request type
+pub fn load_snapshot(config: &JsmConfig) -> Result<Option<SchemaSnapshot>> {
+    read_snapshot(config, &snapshot_path(config))
+}
+
+pub fn save_snapshot(config: &JsmConfig, snapshot: &SchemaSnapshot) -> Result<PathBuf> {
+    write_snapshot(&snapshot_path(config), snapshot)
+}
+
+/// Load the drift baseline, see [`baseline_path`]
+pub fn load_baseline(config: &JsmConfig) -> Result<Option<SchemaSnapshot>> {
+    read_snapshot(config, &baseline_path(config))
+}
+
+pub fn save_baseline(config: &JsmConfig, snapshot: &SchemaSnapshot) -> Result<PathBuf> {
+    write_snapshot(&baseline_path(config), snapshot)
+}
+
+fn read_snapshot(config: &JsmConfig, path: &Path) -> Result<Option<SchemaSnapshot>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read schema snapshot: {}", path.display()))?;
+    let snapshot: SchemaSnapshot = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse schema snapshot: {}", path.display()))?;
+    let matches = snapshot.base_url == config.base_url
+        && snapshot.portal_id == config.portal_id
+        && snapshot.request_type_id == config.request_type_id;
+    Ok(matches.then_some(snapshot))
+}
+
+fn write_snapshot(path: &Path, snapshot: &SchemaSnapshot) -> Result<PathBuf> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(snapshot)?)
+        .with_context(|| format!("Failed to write schema snapshot: {}", path.display()))?;
+    Ok(path.to_path_buf())
+}
+
+/// Read a field list from a snapshot file or from `analyze --output json` output
+pub fn load_fields_file(path: &Path) -> Result<RequestTypeFields> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read schema file: {}", path.display()))?;
+    let mut value: serde_json::Value = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse schema file: {}", path.display()))?;
+    let fields = match value.get_mut("fields") {
+        Some(fields) => fields.take(),
+        None => value,
+    };
+    serde_json::from_value(fields)
+        .with_context(|| format!("Unexpected field list in {}", path.display()))
+}
+
+/// Field list of the configured request type, from a fresh snapshot when there is one.
+/// A live fetch refreshes the snapshot; failing to write it only logs a warning.
+pub async fn cached_request_type_fields(
+    client: &Client,
+    config: &JsmConfig,
+) -> Result<RequestTypeFields> {
+    match fresh_snapshot_fields(config) {
+        Some(fields) => Ok(fields),
+        None => live_request_type_fields(client, config).await,
+    }
+}
+
+/// Field list from the snapshot, if caching is enabled and the snapshot is within its TTL
+pub fn fresh_snapshot_fields(config: &JsmConfig) -> Option<RequestTypeFields> {
+    let ttl_secs = config.schema_cache.ttl_secs;
+    if ttl_secs == 0 {
+        return None;
+    }
+    match load_snapshot(config) {
+        Ok(Some(snapshot)) if snapshot.is_fresh(ttl_secs) => {
+            crate::log_debug!("Using schema snapshot from {}", snapshot.fetched_at);
+            Some(snapshot.fields)
+        }
+        Ok(_) => None,
+        Err(error) => {
+            crate::log_warn!("Ignoring schema snapshot: {:#}", error);
+            None
+        }
+    }
+}
+
+/// Fetch the field list live and refresh the snapshot with it
+pub async fn live_request_type_fields(
+    client: &Client,
+    config: &JsmConfig,
+) -> Result<RequestTypeFields> {
+    let fields = crate::schema::fetch_request_type_fields(client, config).await?;
+    if config.schema_cache.ttl_secs > 0
+        && let Err(error) = save_snapshot(config, &SchemaSnapshot::new(config, fields.clone()))
+    {
+        crate::log_warn!("Failed to cache the request type schema: {:#}", error);
+    }
+    Ok(fields)
+}
+
+/// A field as listed in a schema diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldRef {
+    pub field_id: String,
+    pub name: String,
+    pub required: bool,
+}
+
+impl From<&RequestTypeField> for FieldRef {
+    fn from(field: &RequestTypeField) -> Self {
+        Self {
+            field_id: field.field_id.clone(),
+            name: field.name.clone(),
+            required: field.required,
+        }
+    }
+}
+
+/// A field whose value type changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeChange {
+    pub field: FieldRef,
+    pub before: String,
+    pub after: String,
+}
+
+/// A field whose set of valid values changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OptionChange {
+    pub field: FieldRef,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Differences between two field lists of a request type
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SchemaDiff {
+    pub added: Vec<FieldRef>,
+    pub removed: Vec<FieldRef>,
+    pub newly_required: Vec<FieldRef>,
+    pub no_longer_required: Vec<FieldRef>,
+    pub type_changed: Vec<TypeChange>,
+    pub options_changed: Vec<OptionChange>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Changes that can make an existing ticket file fail: removed fields, new required
+    /// fields, changed types and removed options
+    pub fn is_breaking(&self) -> bool {
+        !self.removed.is_empty()
+            || self.added.iter().any(|field| field.required)
+            || !self.newly_required.is_empty()
+            || !self.type_changed.is_empty()
+            || self
+                .options_changed
+                .iter()
+                .any(|change| !change.removed.is_empty())
+    }
+}
+
+/// Compare an older field list with a newer one
+pub fn diff(before: &RequestTypeFields, after: &RequestTypeFields) -> SchemaDiff {
+    let mut diff = SchemaDiff::default();
+
+    for old in &before.request_type_fields {
+        let Some(new) = after.field(&old.field_id) else {
+            diff.removed.push(old.into());
+            continue;
+        };
+        match (old.required, new.required) {
+            (false, true) => diff.newly_required.push(new.into()),
+            (true, false) => diff.no_longer_required.push(new.into()),
+            _ => {}
+        }
+        if old.type_name() != new.type_name() {
+            diff.type_changed.push(TypeChange {
+                field: new.into(),
+                before: old.type_name(),
+                after: new.type_name(),
+            });
+        }
+
+        let old_values = option_values(old);
+        let new_values = option_values(new);
+        if old_values != new_values {
+            diff.options_changed.push(OptionChange {
+                field: new.into(),
+                added: new_values.difference(&old_values).cloned().collect(),
+                removed: old_values.difference(&new_values).cloned().collect(),
+            });
+        }
+    }
+
+    for new in &after.request_type_fields {
+        if before.field(&new.field_id).is_none() {
+            diff.added.push(new.into());
+        }
+    }
+    diff
+}
+
+/// Valid values of a field by label, children as `Parent / Child`
+fn option_values(field: &RequestTypeField) -> BTreeSet<String> {
+    let mut values = BTreeSet::new();
+    for value in &field.valid_values {
+        values.insert(value.display().to_string());
+        for child in &value.children {
+            values.insert(format!("{} / {}", value.display(), child.display()));
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fields(value: serde_json::Value) -> RequestTypeFields {
+        serde_json::from_value(json!({ "requestTypeFields": value })).unwrap()
+    }
+
+    #[test]
+    fn detects_breaking_changes() {
+        let before = fields(json!([
+            { "fieldId": "summary", "name": "Summary", "required": true,
+              "jiraSchema": { "type": "string" } },
+            { "fieldId": "customfield_1", "name": "Old", "jiraSchema": { "type": "string" } },
+            { "fieldId": "customfield_2", "name": "Risk", "jiraSchema": { "type": "option" },
+              "validValues": [{ "value": "1", "label": "Low" }, { "value": "2", "label": "High" }] },
+            { "fieldId": "customfield_3", "name": "Plan", "jiraSchema": { "type": "string" } }
+        ]));
+        let after = fields(json!([
+            { "fieldId": "summary", "name": "Summary", "required": true,
+              "jiraSchema": { "type": "string" } },
+            { "fieldId": "customfield_2", "name": "Risk", "jiraSchema": { "type": "option" },
+              "validValues": [{ "value": "1", "label": "Low" }, { "value": "3", "label": "Medium" }] },
+            { "fieldId": "customfield_3", "name": "Plan", "required": true,
+              "jiraSchema": { "type": "string" } },
+            { "fieldId": "customfield_4", "name": "Notes", "jiraSchema": { "type": "string" } }
+        ]));
+
+        let diff = diff(&before, &after);
+        assert_eq!(diff.removed[0].field_id, "customfield_1");
+        assert_eq!(diff.added[0].field_id, "customfield_4");
+        assert_eq!(diff.newly_required[0].field_id, "customfield_3");
+        assert_eq!(diff.options_changed[0].added, vec!["Medium"]);
+        assert_eq!(diff.options_changed[0].removed, vec!["High"]);
+        assert!(diff.is_breaking());
+
+        let unchanged = super::diff(&before, &before);
+        assert!(unchanged.is_empty());
+        assert!(!unchanged.is_breaking());
+    }
+
+    #[test]
+    fn added_optional_fields_are_not_breaking() {
+        let before = fields(json!([{ "fieldId": "summary", "name": "Summary" }]));
+        let after = fields(json!([
+            { "fieldId": "summary", "name": "Summary" },
+            { "fieldId": "customfield_4", "name": "Notes" }
+        ]));
+        let diff = diff(&before, &after);
+        assert!(!diff.is_empty());
+        assert!(!diff.is_breaking());
+    }
+
+    #[test]
+    fn snapshots_expire_after_the_ttl() {
+        let config = crate::config::create_default_config();
+        let mut snapshot = SchemaSnapshot::new(&config, RequestTypeFields::default());
+        assert!(snapshot.is_fresh(60));
+        snapshot.fetched_at = (chrono::Utc::now() - chrono::Duration::seconds(120)).to_rfc3339();
+        assert!(!snapshot.is_fresh(60));
+        assert_eq!(
+            snapshot_path(&config),
+            PathBuf::from("jsm_schema_cache.pvt/your_organization_atlassian_net_6_73.json")
+        );
+        assert_eq!(
+            baseline_path(&config),
+            PathBuf::from(
+                "jsm_schema_cache.pvt/your_organization_atlassian_net_6_73.baseline.json"
+            )
+        );
+    }
+}
@@ -0,0 +1,62 @@
+/// Render rows as a plain-text table with left-aligned, padded columns
+pub fn render(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers
+        .iter()
+        .map(|header| header.chars().count())
+        .collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        let last = cells.len().saturating_sub(1);
+        cells
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(index, (cell, width))| {
+                if index == last {
+                    cell.to_string()
+                } else {
+                    format!("{:<width$}", cell, width = width)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    let mut lines = vec![
+        format_row(headers.to_vec()),
+        format_row(separator.iter().map(String::as_str).collect()),
+    ];
+    for row in rows {
+        lines.push(format_row(row.iter().map(String::as_str).collect()));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_columns_to_the_widest_cell() {
+        let table = render(
+            &["Field", "Message"],
+            &[
+                vec!["customfield_10878".to_string(), "Invalid date".to_string()],
+                vec!["summary".to_string(), "Required".to_string()],
+            ],
+        );
+        assert_eq!(
+            table,
+            "Field              Message\n\
+             -----------------  ------------\n\
+             customfield_10878  Invalid date\n\
+             summary            Required"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use crate::schema::{FieldValue, RequestType, RequestTypeField, RequestTypeFields};
+
+/// Allowed values listed in a field comment before the rest are summarised
+const MAX_LISTED_VALUES: usize = 20;
+
+/// Render a ticket file skeleton for a request type. Every visible field gets a comment
+/// with its display name, required flag, type, description and allowed values. Required
+/// fields are written with a placeholder value; optional fields are commented out.
+///
+/// Fields that can be written as arrays of tables (affected services) come last: TOML
+/// merges any key following an array of tables into its last entry.
+pub fn render_ticket_template(request_type: &RequestType, schema: &RequestTypeFields) -> String {
+    let mut out = format!(
+        "# Ticket for \"{}\" (request type {})\n\
+         # Required fields are filled in with placeholders; uncomment optional fields to use them.\n",
+        request_type.name, request_type.id
+    );
+
+    let (table_fields, fields): (Vec<&RequestTypeField>, Vec<&RequestTypeField>) = schema
+        .request_type_fields
+        .iter()
+        .filter(|field| field.visible)
+        .partition(|field| crate::services::is_service_field(field));
+
+    for field in fields {
+        render_field(&mut out, field);
+    }
+    if !table_fields.is_empty() {
+        out.push_str(
+            "\n# Keep these fields at the end: keys after a [[table]] entry belong to it.\n",
+        );
+        for field in table_fields {
+            render_field(&mut out, field);
+        }
+    }
+    out
+}
+
+fn render_field(out: &mut String, field: &RequestTypeField) {
+    out.push('\n');
+    let required = if field.required {
+        "required"
+    } else {
+        "optional"
+    };
+    out.push_str(&format!(
+        "# {} ({}, {})\n",
+        field.name,
+        required,
+        field.type_name()
+    ));
+    for line in field.description.lines().map(str::trim_end) {
+        if !line.trim().is_empty() {
+            out.push_str(&format!("#   {}\n", line));
+        }
+    }
+    if !field.valid_values.is_empty() {
+        let mut listed: Vec<&str> = field
+            .valid_values
+            .iter()
+            .take(MAX_LISTED_VALUES)
+            .map(FieldValue::display)
+            .collect();
+        let more = field.valid_values.len().saturating_sub(MAX_LISTED_VALUES);
+        let more = format!("... and {} more", more);
+        if field.valid_values.len() > MAX_LISTED_VALUES {
+            listed.push(&more);
+        }
+        out.push_str(&format!("#   Allowed values: {}\n", listed.join(", ")));
+    }
+    if crate::services::is_service_field(field) {
+        out.push_str(&format!(
+            "#   Services by name, or as full service objects: [[{}]] with id, name and description\n",
+            field.field_id
+        ));
+    }
+
+    let line = format!("{} = {}\n", field.field_id, placeholder(field));
+    if field.required {
+        out.push_str(&line);
+    } else {
+        out.push_str("# ");
+        out.push_str(&line);
+    }
+}
+
+/// A TOML value of the right shape for the field
+fn placeholder(field: &RequestTypeField) -> String {
+    if crate::services::is_service_field(field) {
+        // Services can be given by name
+        return "[\"Service name\"]".to_string();
+    }
+    match field.jira_schema.field_type.as_str() {
+        "datetime" => "\"tomorrow 09:00\"".to_string(),
+        "number" => "0".to_string(),
+        "option" => field
+            .default_values
+            .first()
+            .or(field.valid_values.first())
+            .map(|option| toml_string(option.display()))
+            .unwrap_or_else(|| "\"\"".to_string()),
+        "array" => "[]".to_string(),
+        _ => "\"\"".to_string(),
+    }
+}
+
+/// Quote a string as a TOML basic string
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn required_fields_are_uncommented() {
+        let request_type = RequestType {
+            id: "73".to_string(),
+            name: "Normal Change".to_string(),
+            ..Default::default()
+        };
+        let schema: RequestTypeFields = serde_json::from_value(json!({
+            "requestTypeFields": [
+                { "fieldId": "summary", "name": "Summary", "required": true,
+                  "jiraSchema": { "type": "string" } },
+                { "fieldId": "customfield_10878", "name": "Planned start", "required": true,
+                  "jiraSchema": { "type": "datetime" } },
+                { "fieldId": "customfield_10005", "name": "Change type",
+                  "jiraSchema": { "type": "option" },
+                  "validValues": [{ "value": "1", "label": "Normal" }] },
+                { "fieldId": "customfield_10243", "name": "Affected services",
+                  "jiraSchema": { "type": "array", "items": "any",
+                    "custom": "com.atlassian.jira.plugins.service-entity:service-entity-field-cftype" } },
+                { "fieldId": "customfield_10883", "name": "Implementation plan",
+                  "description": "Steps to implement\nthe change",
+                  "jiraSchema": { "type": "string" } },
+                { "fieldId": "customfield_10006", "name": "Hidden", "visible": false }
+            ]
+        }))
+        .unwrap();
+
+        let template = render_ticket_template(&request_type, &schema);
+        assert!(template.contains("# Summary (required, string)\nsummary = \"\"\n"));
+        assert!(template.contains("customfield_10878 = \"tomorrow 09:00\"\n"));
+        assert!(template.contains(
+            "# Change type (optional, option)\n#   Allowed values: Normal\n# customfield_10005 = \"Normal\"\n"
+        ));
+        assert!(template.contains("#   Steps to implement\n#   the change\n"));
+        // Services come after every other field
+        assert!(
+            template.find("customfield_10243").unwrap()
+                > template.find("customfield_10883").unwrap()
+        );
+        assert!(!template.contains("customfield_10006"));
+        // The skeleton itself must be valid TOML
+        template.parse::<toml::Table>().unwrap();
+    }
+}
@@ -0,0 +1,315 @@
+use crate::RiskAssessmentConfig;
+use crate::datetime::{self, DateContext};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Where the fields of a ticket come from. Later sources override earlier ones.
+#[derive(Debug, Clone, Default)]
+pub struct TicketSources {
+    /// TOML ticket file (`-t ticket.toml`)
+    pub toml_file: Option<PathBuf>,
+    /// JSON data file (`-j data.json`)
+    pub json_file: Option<PathBuf>,
+    /// `key=value` pairs from the command line (`-d`)
+    pub data: Vec<String>,
+}
+
+/// Load and merge ticket fields from all sources, converting native TOML dates to JSM strings.
+///
+/// Relative `attachments` paths in a file are made relative to that file's directory.
+pub fn load_fields(sources: &TicketSources, ctx: &DateContext) -> Result<HashMap<String, Value>> {
+    let mut fields = HashMap::new();
+
+    if let Some(toml_path) = &sources.toml_file {
+        let mut toml_fields = load_toml_fields(toml_path, ctx)?;
+        resolve_attachment_paths(&mut toml_fields, toml_path)?;
+        crate::log_debug!(
+            "Loaded {} fields from TOML file: {}",
+            toml_fields.len(),
+            toml_path.display()
+        );
+        fields.extend(toml_fields);
+    }
+
+    if let Some(json_path) = &sources.json_file {
+        let mut json_fields = load_json_fields(json_path)?;
+        resolve_attachment_paths(&mut json_fields, json_path)?;
+        crate::log_debug!(
+            "Loaded {} additional fields from JSON file: {}",
+            json_fields.len(),
+            json_path.display()
+        );
+        fields.extend(json_fields);
+    }
+
+    for item in &sources.data {
+        if let Some((key, value)) = item.split_once('=') {
+            fields.insert(key.to_string(), Value::String(value.to_string()));
+        } else {
+            crate::log_warn!(
+                "Invalid data format '{}', expected 'key=value'; ignoring",
+                item
+            );
+        }
+    }
+
+    Ok(fields)
+}
+
+/// Rewrite the `attachments` of the fields loaded from `path` against the file's directory
+fn resolve_attachment_paths(fields: &mut HashMap<String, Value>, path: &Path) -> Result<()> {
+    if !fields.contains_key("attachments") {
+        return Ok(());
+    }
+    let attachments = take_attachments(fields, path.parent())?;
+    fields.insert(
+        "attachments".to_string(),
+        attachments
+            .iter()
+            .map(|attachment| Value::String(attachment.to_string_lossy().into_owned()))
+            .collect(),
+    );
+    Ok(())
+}
+
+/// Remove the `attachments` list from the ticket fields.
+///
+/// Relative paths are resolved against `base_dir`; [`load_fields`] has already done
+/// this for paths from the ticket and data files.
+pub fn take_attachments(
+    fields: &mut HashMap<String, Value>,
+    base_dir: Option<&Path>,
+) -> Result<Vec<PathBuf>> {
+    Ok(take_string_list(fields, "attachments")?
+        .into_iter()
+        .map(|path| {
+            let path = PathBuf::from(path);
+            match base_dir {
+                Some(dir) if path.is_relative() => dir.join(path),
+                _ => path,
+            }
+        })
+        .collect())
+}
+
+/// Remove a string-valued setting (e.g. `raise_on_behalf_of`) from the ticket fields
+pub fn take_string(fields: &mut HashMap<String, Value>, key: &str) -> Result<Option<String>> {
+    match fields.remove(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(other) => Err(anyhow::anyhow!(
+            "'{}' must be a string, got: {}",
+            key,
+            other
+        )),
+    }
+}
+
+/// Remove a list-of-strings setting (e.g. `request_participants`) from the ticket fields.
+/// A single string is accepted as a one-element list.
+pub fn take_string_list(fields: &mut HashMap<String, Value>, key: &str) -> Result<Vec<String>> {
+    let items = match fields.remove(key) {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::Array(items)) => items,
+        Some(single @ Value::String(_)) => vec![single],
+        Some(other) => {
+            return Err(anyhow::anyhow!(
+                "'{}' must be a list of strings, got: {}",
+                key,
+                other
+            ));
+        }
+    };
+
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::String(value) => Ok(value),
+            other => Err(anyhow::anyhow!(
+                "'{}' entries must be strings, got: {}",
+                key,
+                other
+            )),
+        })
+        .collect()
+}
+
+/// Read the `[risk_assessment]` section of a TOML ticket file
+pub fn load_risk_assessment(path: &Path) -> Result<RiskAssessmentConfig> {
+    let toml_content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read TOML file: {}", path.display()))?;
+    let toml_value: toml::Value = toml::from_str(&toml_content)
+        .with_context(|| format!("Failed to parse TOML file: {}", path.display()))?;
+
+    toml_value
+        .get("risk_assessment")
+        .context("Missing 'risk_assessment' section in TOML file")?
+        .clone()
+        .try_into()
+        .with_context(|| {
+            format!(
+                "Failed to parse risk assessment configuration from TOML file: {}",
+                path.display()
+            )
+        })
+}
+
+/// Read a TOML ticket file into top-level fields
+pub fn load_toml_fields(path: &Path, ctx: &DateContext) -> Result<HashMap<String, Value>> {
+    let toml_content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read TOML file: {}", path.display()))?;
+    let toml_value: toml::Value = toml::from_str(&toml_content)
+        .with_context(|| format!("Failed to parse TOML file: {}", path.display()))?;
+
+    match toml_to_json(toml_value, ctx)
+        .with_context(|| format!("Failed to convert TOML file: {}", path.display()))?
+    {
+        Value::Object(map) => Ok(map.into_iter().collect()),
+        _ => Err(anyhow::anyhow!(
+            "TOML file must contain an object at the root level"
+        )),
+    }
+}
+
+/// Read a JSON data file into top-level fields
+pub fn load_json_fields(path: &Path) -> Result<HashMap<String, Value>> {
+    let json_content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read JSON file: {}", path.display()))?;
+    let json_value: Value = serde_json::from_str(&json_content)
+        .with_context(|| format!("Failed to parse JSON file: {}", path.display()))?;
+
+    match json_value {
+        Value::Object(map) => Ok(map.into_iter().collect()),
+        _ => Err(anyhow::anyhow!(
+            "JSON file must contain an object at the root level"
+        )),
+    }
+}
+
+/// Convert a parsed TOML document into JSON. Native TOML datetimes become JSM
+/// date/datetime strings at any depth.
+///
+/// Relative time expressions (`now+2h`, `tomorrow 14:00`) are left as strings; they are
+/// expanded once the field types are known, see [`datetime::resolve_date_fields`].
+pub fn toml_to_json(value: toml::Value, ctx: &DateContext) -> Result<Value> {
+    Ok(match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => serde_json::Number::from_f64(f)
+            .map(Value::Number)
+            .with_context(|| format!("Float value {} cannot be represented in JSON", f))?,
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(datetime::from_toml_datetime(&dt, ctx)?),
+        toml::Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| toml_to_json(item, ctx))
+                .collect::<Result<_>>()?,
+        ),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| Ok((key, toml_to_json(value, ctx)?)))
+                .collect::<Result<_>>()?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn takes_attachments_relative_to_ticket_dir() {
+        let mut fields = HashMap::from([
+            ("summary".to_string(), json!("Deploy")),
+            (
+                "attachments".to_string(),
+                json!(["runbook.pdf", "/tmp/diagram.png"]),
+            ),
+        ]);
+        let attachments = take_attachments(&mut fields, Some(Path::new("changes"))).unwrap();
+        assert_eq!(
+            attachments,
+            vec![
+                PathBuf::from("changes/runbook.pdf"),
+                PathBuf::from("/tmp/diagram.png")
+            ]
+        );
+        assert!(!fields.contains_key("attachments"));
+    }
+
+    #[test]
+    fn resolves_attachments_against_their_own_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml_file = dir.path().join("ticket.toml");
+        let json_file = dir.path().join("sub").join("data.json");
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(
+            &toml_file,
+            "summary = \"Deploy\"\nattachments = [\"runbook.pdf\"]\n",
+        )
+        .unwrap();
+        std::fs::write(&json_file, r#"{ "attachments": ["a.log"] }"#).unwrap();
+
+        let sources = TicketSources {
+            toml_file: Some(toml_file),
+            json_file: None,
+            data: Vec::new(),
+        };
+        let mut fields = load_fields(&sources, &ctx()).unwrap();
+        assert_eq!(
+            take_attachments(&mut fields, None).unwrap(),
+            vec![dir.path().join("runbook.pdf")]
+        );
+
+        // The data file overrides the ticket file's list, relative to its own directory
+        let sources = TicketSources {
+            json_file: Some(json_file),
+            ..sources
+        };
+        let mut fields = load_fields(&sources, &ctx()).unwrap();
+        assert_eq!(
+            take_attachments(&mut fields, None).unwrap(),
+            vec![dir.path().join("sub").join("a.log")]
+        );
+    }
+
+    fn ctx() -> DateContext {
+        DateContext {
+            now: "2025-09-16T21:15:00Z".parse().unwrap(),
+            timezone: Some(chrono_tz::Pacific::Auckland),
+        }
+    }
+
+    #[test]
+    fn converts_native_toml_dates_only() {
+        let toml_value: toml::Value = toml::from_str(
+            r#"
+summary = "now is a good time"
+customfield_10878 = 2025-09-23T14:00:00+13:00
+customfield_10879 = "now+2h"
+duedate = 2025-09-30
+
+[[customfield_10243]]
+name = "now"
+"#,
+        )
+        .unwrap();
+
+        let json = toml_to_json(toml_value, &ctx()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "summary": "now is a good time",
+                "customfield_10878": "2025-09-23T14:00:00.000+1300",
+                "customfield_10879": "now+2h",
+                "duedate": "2025-09-30",
+                "customfield_10243": [ { "name": "now" } ]
+            })
+        );
+    }
+}
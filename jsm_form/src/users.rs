@@ -0,0 +1,174 @@
+use crate::JsmConfig;
+use crate::error::JsmError;
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::Deserialize;
+
+/// An Atlassian account as returned by the user search API
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[serde(rename = "emailAddress", default)]
+    pub email_address: Option<String>,
+    #[serde(rename = "displayName", default)]
+    pub display_name: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// Returns true if the value looks like an email address rather than an account ID
+pub fn is_email(value: &str) -> bool {
+    value.contains('@')
+}
+
+/// Pick the account showing exactly the searched email address from user search results.
+/// Accounts whose address is hidden by their privacy settings can't be checked, so they
+/// are never picked.
+pub fn pick_account<'a>(email: &str, users: &'a [User]) -> std::result::Result<&'a User, String> {
+    let exact: Vec<&User> = users
+        .iter()
+        .filter(|user| user.active)
+        .filter(|user| {
+            user.email_address
+                .as_deref()
+                .is_some_and(|address| address.eq_ignore_ascii_case(email))
+        })
+        .collect();
+
+    match exact.as_slice() {
+        [user] => Ok(user),
+        [] => Err(format!(
+            "No active Atlassian account shows the address '{}' (it may be hidden by the account's privacy settings); use the account ID instead",
+            email
+        )),
+        several => Err(format!(
+            "'{}' matches several accounts ({}); use the account ID instead",
+            email,
+            several
+                .iter()
+                .map(|user| format!("{} <{}>", user.display_name, user.account_id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Resolve an email address to an account ID. Account IDs are returned unchanged.
+pub async fn resolve_account_id(client: &Client, config: &JsmConfig, user: &str) -> Result<String> {
+    if !is_email(user) {
+        return Ok(user.to_string());
+    }
+
+    let users = search_users(client, config, user).await?;
+    let account = pick_account(user, &users).map_err(|message| anyhow::anyhow!(message))?;
+    crate::log_debug!(
+        "Resolved {} to {} ({})",
+        user,
+        account.account_id,
+        account.display_name
+    );
+    Ok(account.account_id.clone())
+}
+
+/// Search Atlassian accounts by email address or name
+pub async fn search_users(client: &Client, config: &JsmConfig, user: &str) -> Result<Vec<User>> {
+    let search_url = format!("{}/rest/api/3/user/search", config.base_url);
+    let response = crate::http::send(
+        &config.http,
+        client
+            .get(&search_url)
+            .query(&[("query", user)])
+            .basic_auth(
+                &config.auth.username,
+                Some(&config.auth.token_atlassian_api),
+            )
+            .header("Accept", "application/json"),
+    )
+    .await
+    .with_context(|| format!("Failed to search for user '{}'", user))?;
+
+    if !response.status().is_success() {
+        return Err(JsmError::from_response(response).await.into());
+    }
+
+    response
+        .json()
+        .await
+        .context("Failed to parse user search response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(account_id: &str, email: Option<&str>) -> User {
+        User {
+            account_id: account_id.to_string(),
+            email_address: email.map(str::to_string),
+            display_name: account_id.to_uppercase(),
+            active: true,
+        }
+    }
+
+    #[test]
+    fn picks_account_by_email() {
+        let users = vec![
+            user("a1", Some("ann@example.com")),
+            user("a2", Some("ann.other@example.com")),
+        ];
+        assert_eq!(
+            pick_account("Ann@Example.com", &users).unwrap().account_id,
+            "a1"
+        );
+
+        // A hidden address can't be checked, so even a single such account is not picked
+        let hidden = vec![user("b1", None)];
+        assert!(
+            pick_account("bob@example.com", &hidden)
+                .unwrap_err()
+                .contains("use the account ID instead")
+        );
+
+        assert!(pick_account("nobody@example.com", &[]).is_err());
+        let mixed = vec![user("d1", Some("dave.smith@example.com")), user("d2", None)];
+        assert!(
+            pick_account("dave@example.com", &mixed)
+                .unwrap_err()
+                .contains("use the account ID instead")
+        );
+        let duplicates = vec![
+            user("c1", Some("carol@example.com")),
+            user("c2", Some("carol@example.com")),
+        ];
+        assert!(
+            pick_account("carol@example.com", &duplicates)
+                .unwrap_err()
+                .contains("several accounts")
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_user_searches_are_typed_errors() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rest/api/3/user/search"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let mut config = crate::config::create_default_config();
+        config.base_url = server.uri();
+        let error = resolve_account_id(&Client::new(), &config, "ann@example.com")
+            .await
+            .unwrap_err();
+        assert_eq!(error.downcast_ref::<JsmError>().unwrap().exit_code(), 4);
+    }
+}
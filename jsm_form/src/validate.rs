@@ -0,0 +1,243 @@
+use crate::schema::{FieldIssue, IssueKind, RequestTypeFields};
+use crate::{FormData, JsmConfig};
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Check ticket data without network access: against the field schema when one is
+/// available, and against local rules (dates in order, summary not empty, attachments exist)
+pub fn validate_ticket(
+    config: &JsmConfig,
+    form_data: FormData,
+    schema: Option<&RequestTypeFields>,
+) -> Result<Vec<FieldIssue>> {
+    let mut issues = Vec::new();
+    if let Err(error) = crate::attachment::check_attachments(&form_data.attachments) {
+        issues.push(issue("attachments", None, format!("{:#}", error)));
+    }
+
+    let mut payload = crate::form::build_payload(config, form_data)?;
+
+    match schema {
+        Some(schema) => {
+            issues.extend(crate::form::people_issues(&payload, schema));
+            issues.extend(crate::form::resolve_offline(config, &mut payload, schema)?);
+            issues.extend(crate::schema::validate_fields(
+                &payload.request_field_values,
+                schema,
+            ));
+        }
+        // JSM always requires a summary, whatever the request type
+        None => {
+            issues.extend(crate::aliases::check_field_ids(
+                &payload.request_field_values,
+            ));
+            if payload
+                .request_field_values
+                .get("summary")
+                .and_then(Value::as_str)
+                .is_none_or(|summary| summary.trim().is_empty())
+            {
+                issues.push(FieldIssue {
+                    kind: IssueKind::MissingRequired,
+                    ..issue("summary", Some("Summary"), "is required")
+                });
+            }
+        }
+    }
+
+    issues.extend(check_date_order(
+        &payload.request_field_values,
+        &field_names(config, schema),
+    ));
+    Ok(issues)
+}
+
+/// Display names of fields: from the schema, or else the config's aliases
+fn field_names(config: &JsmConfig, schema: Option<&RequestTypeFields>) -> BTreeMap<String, String> {
+    match schema {
+        Some(schema) => schema
+            .request_type_fields
+            .iter()
+            .map(|field| (field.field_id.clone(), field.name.clone()))
+            .collect(),
+        None => config
+            .aliases
+            .iter()
+            .map(|(alias, field_id)| (field_id.clone(), alias.clone()))
+            .collect(),
+    }
+}
+
+/// Fields named `<x> start` must not be after the matching `<x> end`, e.g. Planned start
+/// and Planned end
+pub fn check_date_order(
+    fields: &BTreeMap<String, Value>,
+    names: &BTreeMap<String, String>,
+) -> Vec<FieldIssue> {
+    let mut starts = BTreeMap::new();
+    let mut ends = BTreeMap::new();
+    for (field_id, name) in names {
+        let key = crate::aliases::alias_for_name(name);
+        if let Some(prefix) = key.strip_suffix("_start") {
+            starts.insert(prefix.to_string(), field_id);
+        } else if let Some(prefix) = key.strip_suffix("_end") {
+            ends.insert(prefix.to_string(), field_id);
+        }
+    }
+
+    let mut issues = Vec::new();
+    for (prefix, start_id) in starts {
+        let Some(end_id) = ends.get(&prefix) else {
+            continue;
+        };
+        let start = fields.get(start_id.as_str()).and_then(parse_date);
+        let end = fields.get(end_id.as_str()).and_then(parse_date);
+        if let (Some(start), Some(end)) = (start, end)
+            && start >= end
+        {
+            issues.push(issue(
+                end_id,
+                Some(&names[*end_id]),
+                format!("must be after {} ({})", names[start_id], start_id),
+            ));
+        }
+    }
+    issues
+}
+
+/// Parse a JSM datetime (`2025-09-23T14:00:00.000+1300`) or date (`2025-09-23`)
+fn parse_date(value: &Value) -> Option<DateTime<FixedOffset>> {
+    let text = value.as_str()?.trim();
+    DateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .ok()
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+            Some(date.and_hms_opt(0, 0, 0)?.and_utc().fixed_offset())
+        })
+}
+
+/// Check the `[risk_assessment]` section of a TOML ticket file, if it has one: unknown
+/// keys are rejected by the risk assessment config types, and values must not be empty
+pub fn check_risk_assessment(path: &Path) -> Result<Vec<FieldIssue>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read TOML file: {}", path.display()))?;
+    let table: toml::Table = toml::from_str(&contents)
+        .with_context(|| format!("Failed to parse TOML file: {}", path.display()))?;
+    let Some(section) = table.get("risk_assessment") else {
+        return Ok(Vec::new());
+    };
+
+    let config = match section.clone().try_into::<crate::RiskAssessmentConfig>() {
+        Ok(config) => config,
+        Err(error) => {
+            return Ok(vec![issue("risk_assessment", None, error.message().trim())]);
+        }
+    };
+    let mut issues = Vec::new();
+    for (table, values) in toml::Table::try_from(&config)? {
+        for (key, value) in values.as_table().into_iter().flatten() {
+            if value.as_str().is_some_and(|text| text.trim().is_empty()) {
+                issues.push(issue(
+                    &format!("risk_assessment.{}.{}", table, key),
+                    None,
+                    "is empty",
+                ));
+            }
+        }
+    }
+    Ok(issues)
+}
+
+fn issue(field_id: &str, field_name: Option<&str>, message: impl Into<String>) -> FieldIssue {
+    FieldIssue {
+        field_id: field_id.to_string(),
+        field_name: field_name.map(str::to_string),
+        kind: IssueKind::Other,
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn planned_end_must_follow_planned_start() {
+        let names = BTreeMap::from([
+            ("customfield_10878".to_string(), "Planned start".to_string()),
+            ("customfield_10879".to_string(), "Planned end".to_string()),
+        ]);
+        let mut fields = BTreeMap::from([
+            (
+                "customfield_10878".to_string(),
+                json!("2025-09-23T16:00:00.000+1300"),
+            ),
+            (
+                "customfield_10879".to_string(),
+                json!("2025-09-23T14:00:00.000+1300"),
+            ),
+        ]);
+        let issues = check_date_order(&fields, &names);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field_id, "customfield_10879");
+        assert!(issues[0].message.contains("Planned start"));
+
+        fields.insert(
+            "customfield_10879".to_string(),
+            json!("2025-09-23T18:00:00.000+1300"),
+        );
+        assert!(check_date_order(&fields, &names).is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_keys_without_a_schema() {
+        let mut config = crate::config::create_default_config();
+        config
+            .aliases
+            .insert("planned_start".to_string(), "customfield_10878".to_string());
+        let form_data = FormData::new(std::collections::HashMap::from([
+            ("summary".to_string(), json!("Deploy")),
+            ("planned_start".to_string(), json!("2025-09-23")),
+            ("planed_end".to_string(), json!("2025-09-24")),
+        ]));
+        let issues = validate_ticket(&config, form_data, None).unwrap();
+        let fields: Vec<&str> = issues.iter().map(|issue| issue.field_id.as_str()).collect();
+        assert_eq!(fields, vec!["planed_end"]);
+    }
+
+    #[test]
+    fn checks_the_risk_assessment_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ticket.toml");
+        let check = |section: &str| {
+            std::fs::write(&path, format!("summary = \"x\"\n\n{}", section)).unwrap();
+            check_risk_assessment(&path).unwrap()
+        };
+
+        let issues = check(
+            "[risk_assessment.change_impact_assessment]\n\
+             security_controls_impact = \"Low\"\nsecurity_impact = \"Low\"\n",
+        );
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field_id, "risk_assessment");
+        assert!(
+            issues[0]
+                .message
+                .contains("unknown field `security_impact`"),
+            "{}",
+            issues[0].message
+        );
+
+        let issues =
+            check("[risk_assessment.change_impact_assessment]\nsecurity_controls_impact = \"\"\n");
+        let fields: Vec<&str> = issues.iter().map(|issue| issue.field_id.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec!["risk_assessment.change_impact_assessment.security_controls_impact"]
+        );
+    }
+}
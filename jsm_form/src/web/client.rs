@@ -6,6 +6,7 @@ use std::thread;
 use std::time::Duration;
 
 use crate::JsmConfig;
+use crate::error::JsmError;
 
 use super::login;
 use super::types::RiskAssessmentConfig;
@@ -62,14 +63,33 @@ impl JsmWebClient {
         &mut self,
         ticket_id: &str,
         config: &RiskAssessmentConfig,
+    ) -> crate::error::Result<()> {
+        let ticket_url = format!("{}/browse/{}", self.config.base_url, ticket_id);
+        self.run_risk_assessment(ticket_id, &ticket_url, config)
+            .map_err(|source| {
+                // Report the page the browser was on, falling back to the ticket page
+                let url = self
+                    .tab
+                    .as_ref()
+                    .map(|tab| tab.get_url())
+                    .filter(|url| !url.is_empty() && url != "about:blank")
+                    .unwrap_or(ticket_url);
+                JsmError::Browser { url, source }
+            })
+    }
+
+    fn run_risk_assessment(
+        &mut self,
+        ticket_id: &str,
+        ticket_url: &str,
+        config: &RiskAssessmentConfig,
     ) -> Result<()> {
         crate::log_info!("Starting risk assessment for ticket: {}", ticket_id);
         let tab = self.get_tab()?;
 
-        let ticket_url = format!("{}/browse/{}", self.config.base_url, ticket_id);
         self.count_nav += 1;
         crate::log_info!("Navigating #{} to: {}", self.count_nav, ticket_url);
-        tab.navigate_to(&ticket_url)?;
+        tab.navigate_to(ticket_url)?;
         tab.wait_until_navigated()?;
 
         crate::log_info!("Verifying ticket page URL...");
@@ -282,7 +302,7 @@ pub fn complete_risk_assessment(
     config: &JsmConfig,
     ticket_id: &str,
     risk_config: &RiskAssessmentConfig,
-) -> Result<()> {
+) -> crate::error::Result<()> {
     let mut client = JsmWebClient::new(config.clone());
     client.complete_risk_assessment(ticket_id, risk_config)
 }
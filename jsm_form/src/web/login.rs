@@ -37,14 +37,15 @@ pub(crate) fn wait_for_ticket_page(
         if is_on_ticket_page(&new_url, ticket_id) {
             return Ok(true);
         }
-        if new_url == current_url {
-            if !warned_same_url && start_time.elapsed() > Duration::from_secs(10) {
-                crate::log_warn!(
-                    "Login URL has remained at {} for over 10 seconds; continuing to monitor in case manual action is required.",
-                    new_url
-                );
-                warned_same_url = true;
-            }
+        if new_url == current_url
+            && !warned_same_url
+            && start_time.elapsed() > Duration::from_secs(10)
+        {
+            crate::log_warn!(
+                "Login URL has remained at {} for over 10 seconds; continuing to monitor in case manual action is required.",
+                new_url
+            );
+            warned_same_url = true;
         }
 
         if new_url.starts_with("https://id.atlassian.com/") && new_url.contains("login") {
@@ -95,7 +96,7 @@ pub(crate) fn wait_for_ticket_page(
                     "Microsoft 2FA reprocess detected (URL: {}). Waiting for user to complete multi-factor authentication...",
                     new_url
                 );
-                print!(
+                eprint!(
                     "Please complete any required multi-factor authentication in the opened browser window. "
                 );
                 std::thread::sleep(Duration::from_millis(10000));
@@ -267,14 +268,13 @@ pub(crate) fn try_fill_microsoft_username(tab: &Arc<Tab>, username: &str) -> Res
     tab.send_character(username)
         .context("Failed to type Microsoft username")?;
 
-    if tab.press_key("Enter").is_err() {
-        if let Ok(button) =
+    if tab.press_key("Enter").is_err()
+        && let Ok(button) =
             tab.wait_for_element_with_custom_timeout("#idSIButton9", Duration::from_secs(2))
-        {
-            crate::log_info!("Clicking Microsoft Next button directly");
-            button.scroll_into_view()?;
-            button.click()?;
-        }
+    {
+        crate::log_info!("Clicking Microsoft Next button directly");
+        button.scroll_into_view()?;
+        button.click()?;
     }
 
     Ok(true)
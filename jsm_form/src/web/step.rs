@@ -31,16 +31,16 @@ impl StepController {
 
         if self.skip_steps.contains(&step_number) {
             crate::log_info!("Skipping interactive step {}: {}", step_number, description);
-            println!("\n--- Skipping Step {}: {}", step_number, description);
+            eprintln!("\n--- Skipping Step {}: {}", step_number, description);
             return Ok(());
         }
 
     crate::log_info!("Interactive step {}: {}", step_number, description);
-        println!("\n=== Step {}: {} ===", step_number, description);
-        print!("Press Enter to continue...");
-        io::stdout()
+        eprintln!("\n=== Step {}: {} ===", step_number, description);
+        eprint!("Press Enter to continue...");
+        io::stderr()
             .flush()
-            .context("Failed to flush stdout during step-through pause")?;
+            .context("Failed to flush stderr during step-through pause")?;
         let mut input = String::new();
         io::stdin()
             .read_line(&mut input)
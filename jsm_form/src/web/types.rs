@@ -1,12 +1,14 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct RiskAssessmentConfig {
     pub change_impact_assessment: ChangeImpactAssessmentConfig,
     pub change_risk_assessment: Option<ChangeRiskAssessmentConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct ChangeImpactAssessmentConfig {
     pub security_controls_impact: Option<String>,
     pub performance_impact: Option<String>,
@@ -14,6 +16,7 @@ pub struct ChangeImpactAssessmentConfig {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct ChangeRiskAssessmentConfig {
     // Placeholder for future expansion
 }
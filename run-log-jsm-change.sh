@@ -14,16 +14,6 @@ done
 
 echo "# Using ticket.toml as input for change"
 
-output="$( cargo run -- submit -t ticket.toml | tee /dev/tty )"
-
-# Extract the Request ID from the output e.g. Request ID: ITH-66778
-request_id="$( echo "$output" | grep 'Request ID: ' | awk -F 'Request ID: ' '{print $2}' )"
-if [ -z "$request_id" ]; then
-  echo "Error: Could not extract Request ID from output."
-  exit 1
-fi
-echo "Extracted Request ID: $request_id"
-sleep 2; echo
-
-echo "# Running risk assessment for change $request_id"
-cargo run -- risk-assessment -i "$request_id" -t ticket.toml
+# Creates the request, waits for it to be viewable and completes the risk assessment.
+# Extra arguments are passed through, e.g. --skip-risk-assessment or --output json.
+exec cargo run -- change -t ticket.toml "$@"